@@ -0,0 +1,125 @@
+import test from 'ava'
+import axios from 'axios';
+import zlib from 'node:zlib';
+
+const Server = axios.create({
+    baseURL: 'http://0.0.0.0:8080/',
+    decompress: false,
+    responseType: 'arraybuffer',
+  });
+
+import * as Walker from '../index.js'
+
+const config = {
+    url: "0.0.0.0:8080",
+    worker_threads: "1",
+    compression: "br,gzip,zstd",
+    compression_min_size: "256",
+}
+
+const LARGE_TEXT = "Walker compresses this line. ".repeat(100);
+const LARGE_OBJECT = { items: Array.from({ length: 100 }, (_, id) => ({ id, name: `item ${id}` })) };
+
+test.serial.before(async (_) => {
+    Walker.get("/large/text", (res) => {
+        res.sendText(LARGE_TEXT);
+    });
+
+    Walker.get("/large/json", (res) => {
+        res.sendObject(LARGE_OBJECT);
+    });
+
+    Walker.get("/large/binary", (res) => {
+        res.sendBytes(Buffer.from(LARGE_TEXT), "application/octet-stream");
+    });
+
+    Walker.get("/large/vary", (res) => {
+        res.setHeader("Vary", "Accept-Encoding");
+        res.sendText(LARGE_TEXT);
+    });
+
+    Walker.get("/large/etag", (res) => {
+        res.setHeader("ETag", '"v1"');
+        res.sendText(LARGE_TEXT);
+    });
+
+    Walker.get("/large/uncompressed", (res) => {
+        res.sendText(LARGE_TEXT);
+    }, { compress: false });
+
+    Walker.get("/small", (res) => {
+        res.sendText("tiny");
+    });
+
+    Walker.startWithConfig(config);
+
+    // Sleeep for 100ms to let server start
+    await new Promise((resolve) => setTimeout(resolve, 100));
+});
+
+const getWith = (path, encoding) => Server.get(path, { headers: { "Accept-Encoding": encoding } });
+
+test("Responses use the encoding with the highest q-value", async t => {
+    const gzip = await getWith("/large/text", "gzip, br;q=0.5");
+    t.is(gzip.headers['content-encoding'], "gzip");
+    t.is(gzip.headers['vary'], "accept-encoding");
+    t.is(zlib.gunzipSync(gzip.data).toString(), LARGE_TEXT);
+
+    const brotli = await getWith("/large/json", "gzip;q=0.8, br");
+    t.is(brotli.headers['content-encoding'], "br");
+    t.deepEqual(JSON.parse(zlib.brotliDecompressSync(brotli.data).toString()), LARGE_OBJECT);
+});
+
+test("Ties and wildcards use the order from the config", async t => {
+    const tie = await getWith("/large/text", "gzip, br");
+    t.is(tie.headers['content-encoding'], "br");
+
+    const wildcard = await getWith("/large/text", "*;q=0.5, br;q=0");
+    t.is(wildcard.headers['content-encoding'], "gzip");
+
+    const zstd = await getWith("/large/text", "zstd");
+    t.is(zstd.headers['content-encoding'], "zstd");
+});
+
+test("Unsupported encodings are sent uncompressed with a Vary header", async t => {
+    const response = await getWith("/large/text", "deflate, identity");
+
+    t.is(response.headers['content-encoding'], undefined);
+    t.is(response.headers['vary'], "accept-encoding");
+    t.is(Buffer.from(response.data).toString(), LARGE_TEXT);
+});
+
+test("Small bodies and other content types are not compressed", async t => {
+    const small = await getWith("/small", "gzip");
+    t.is(small.headers['content-encoding'], undefined);
+    t.is(small.headers['vary'], undefined);
+    t.is(Buffer.from(small.data).toString(), "tiny");
+
+    const binary = await getWith("/large/binary", "gzip");
+    t.is(binary.headers['content-encoding'], undefined);
+    t.is(Buffer.from(binary.data).toString(), LARGE_TEXT);
+});
+
+test("A Vary header from the handler is not repeated", async t => {
+    const response = await getWith("/large/vary", "gzip");
+
+    t.is(response.headers['content-encoding'], "gzip");
+    t.is(response.headers['vary'], "Accept-Encoding");
+});
+
+test("Compressed responses have a weak ETag", async t => {
+    const compressed = await getWith("/large/etag", "gzip");
+    t.is(compressed.headers['content-encoding'], "gzip");
+    t.is(compressed.headers['etag'], 'W/"v1"');
+
+    const plain = await getWith("/large/etag", "identity");
+    t.is(plain.headers['content-encoding'], undefined);
+    t.is(plain.headers['etag'], '"v1"');
+});
+
+test("Routes can opt out of compression", async t => {
+    const response = await getWith("/large/uncompressed", "gzip, br");
+
+    t.is(response.headers['content-encoding'], undefined);
+    t.is(Buffer.from(response.data).toString(), LARGE_TEXT);
+});
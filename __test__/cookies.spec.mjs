@@ -0,0 +1,61 @@
+import test from 'ava'
+import axios from 'axios';
+
+const Server = axios.create({
+    baseURL: 'http://0.0.0.0:8080/'
+  });
+
+import * as Walker from '../index.js'
+
+const config = {
+    url: "0.0.0.0:8080",
+    worker_threads: "1",
+    cookie_secret: "a-test-secret-which-is-at-least-32-bytes-long"
+}
+
+const cookiePair = (header) => header.split(";")[0];
+
+test.serial.before(async (_) => {
+    Walker.get("/secure/set", (res) => {
+        res.setCookie("signed", "signed value", { signed: true });
+        res.setCookie("private", "private value", { encrypted: true, httpOnly: true });
+        res.sendText("ok");
+    });
+
+    Walker.get("/secure/get", (res) => {
+        res.sendObject({
+            signed: res.getSignedCookie("signed"),
+            private: res.getEncryptedCookie("private"),
+        });
+    });
+
+    Walker.startWithConfig(config);
+
+    // Sleeep for 100ms to let server start
+    await new Promise((resolve) => setTimeout(resolve, 100));
+});
+
+test("Signed and encrypted cookies round trip", async t => {
+    const set = await Server.get("/secure/set");
+    const cookies = set.headers['set-cookie'];
+
+    t.is(cookies.length, 2);
+    t.false(cookies[1].includes("private%20value"));
+
+    const cookie = cookies.map(cookiePair).join("; ");
+    const response = await Server.get("/secure/get", { headers: { cookie } });
+
+    t.deepEqual(response.data, { signed: "signed value", private: "private value" });
+});
+
+test("Tampered cookies are rejected", async t => {
+    const set = await Server.get("/secure/set");
+    const cookie = set.headers['set-cookie']
+        .map(cookiePair)
+        .map((pair) => pair.slice(0, -2) + "xx")
+        .join("; ");
+
+    const response = await Server.get("/secure/get", { headers: { cookie } });
+
+    t.deepEqual(response.data, { signed: null, private: null });
+});
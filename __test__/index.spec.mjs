@@ -1,7 +1,11 @@
 import test from 'ava'
 import axios from 'axios';
+import fs from 'node:fs';
+import http from 'node:http';
+import zlib from 'node:zlib';
 
 import registerRoutes from './standard_rig.mjs';
+import { closeReason, connect, OPCODES } from './ws_client.mjs';
 
 import * as Walker from '../index.js'
 
@@ -81,6 +85,665 @@ test("Get /params returns params", async t => {
   t.deepEqual(json, expecting);
 });
 
+test("Get /params decodes values and keeps repeated keys", async t => {
+  const response = await Server.get("/params?name=walker+server&tag=a%26b&tag=c&flag&eq=a=b");
+
+  t.deepEqual(response.data, {
+    name: "walker server",
+    tag: ["a&b", "c"],
+    flag: "",
+    eq: "a=b",
+  });
+});
+
+test("Get /nestedParams expands bracket keys", async t => {
+  const query = "filter%5Bstatus%5D=open&ids%5B%5D=1&ids%5B%5D=2";
+  const response = await Server.get(`/nestedParams?${query}`);
+
+  t.is(response.data.raw, query);
+  t.deepEqual(response.data.params, { filter: { status: "open" }, ids: ["1", "2"] });
+});
+
+test("Get /nestedParams stops expanding deeply nested keys", async t => {
+  const deep = await Server.get(`/nestedParams?${encodeURIComponent("a[b][c][d][e][f][g][h]")}=1`);
+  t.deepEqual(deep.data.params, { a: { b: { c: { d: { e: { f: { "[g][h]": "1" } } } } } } });
+
+  const brackets = "[]".repeat(1_000);
+  const response = await Server.get(`/nestedParams?${encodeURIComponent(`a${brackets}`)}=1`);
+  t.deepEqual(response.data.params, { a: [[[[[{ [brackets.slice(10)]: "1" }]]]]] });
+});
+
+test("Put /requestInfo returns the request metadata", async t => {
+  const response = await Server.put("/requestInfo/7?a=1", "", { headers: { 'x-tag': ['one', 'two'] } });
+  const info = response.data;
+
+  t.is(info.method, "PUT");
+  t.is(info.path, "/requestInfo/7");
+  t.regex(info.url, /^http:\/\/[^/]+\/requestInfo\/7\?a=1$/);
+  t.is(info.version, "HTTP/1.1");
+  t.is(info.scheme, "http");
+  t.regex(info.peerIp, /^(127\.0\.0\.1|::1|::ffff:127\.0\.0\.1)$/);
+  t.true(info.peerAddress.includes(info.peerIp));
+  t.deepEqual(info.tags, ["one", "two"]);
+});
+
+test("Put /requestInfo ignores forwarding headers from untrusted peers", async t => {
+  const headers = { 'x-forwarded-for': '203.0.113.5', 'x-forwarded-proto': 'https' };
+  const response = await Server.put("/requestInfo/1", "", { headers });
+  const info = response.data;
+
+  t.is(info.clientIp, info.peerIp);
+  t.is(info.clientScheme, "http");
+  t.true(info.url.startsWith("http://"));
+});
+
+test("Get /cookies reads and sets cookies", async t => {
+  const response = await Server.get("/cookies", { headers: { cookie: "session=abc%20123; theme=dark" } });
+
+  t.deepEqual(response.data.all, { session: "abc 123", theme: "dark" });
+  t.is(response.data.session, "abc 123");
+  t.deepEqual(response.headers['set-cookie'], [
+    "first=one; HttpOnly; Path=/",
+    "second=two%20words; SameSite=Lax; Secure; Max-Age=60",
+  ]);
+});
+
+test("Get /responseHeaders appends, replaces and removes headers", async t => {
+  const response = await Server.get("/responseHeaders", { responseType: "text" });
+
+  t.is(response.headers['link'], "</a>; rel=preload, </b>; rel=preload");
+  t.is(response.headers['x-replaced'], "second");
+  t.is(response.headers['vary'], "Accept, Origin");
+  t.is(response.headers['content-type'], "text/csv");
+  t.is(response.headers['x-removed'], undefined);
+  t.is(response.headers['server'], undefined);
+
+  t.deepEqual(JSON.parse(response.data), {
+    link: ["</a>; rel=preload", "</b>; rel=preload"],
+    "x-replaced": "second",
+    vary: ["Accept", "Origin"],
+    "content-type": "text/csv",
+  });
+});
+
+test("Get /invalidHeader rejects invalid header names and values", async t => {
+  const response = await Server.get("/invalidHeader");
+
+  t.deepEqual(response.data.errors, [
+    "Invalid value for header: x-split",
+    "Invalid header name: bad name",
+    "Invalid value for header: x-bad",
+  ]);
+  t.deepEqual(response.data.pending, {});
+  t.is(response.headers['set-cookie'], undefined);
+});
+
+test("Get /json/nested serializes values the same as JSON.stringify", async t => {
+  const response = await Server.get("/json/nested", { responseType: "text" });
+
+  const expected = {
+    text: "quote \" slash \\ line\n tab\t \u0001 ünï",
+    numbers: [1, -2, 1.5, 1e21, 2 ** 53, NaN, Infinity],
+    nested: { list: [{ deep: [true, false, null] }], empty: {}, none: [] },
+    skipped: undefined,
+    fn: () => 1,
+    holes: [undefined, () => 1, Symbol("x")],
+    date: new Date(Date.UTC(2024, 0, 2, 3, 4, 5)),
+    custom: { toJSON: (key) => `custom:${key}` },
+  };
+
+  const parsed = JSON.parse(response.data);
+  t.is(parsed.buffer, Buffer.from("walker").toString("base64"));
+  delete parsed.buffer;
+
+  t.deepEqual(parsed, JSON.parse(JSON.stringify(expected)));
+  t.is(parsed.custom, "custom:custom");
+});
+
+test("Get /json/inherited only writes own enumerable properties", async t => {
+  const response = await Server.get("/json/inherited", { responseType: "text" });
+
+  t.is(response.data, '{"value":{"2":"index","own":"kept"},"point":{"x":1}}');
+});
+
+test("Get /json/top sends a top level array", async t => {
+  const response = await Server.get("/json/top");
+
+  t.deepEqual(response.data, [1, "two", { three: 3 }]);
+});
+
+test("Get /json/errors rejects circular objects and BigInt", async t => {
+  const response = await Server.get("/json/errors");
+
+  t.deepEqual(response.data.errors, [
+    "Converting circular structure to JSON",
+    "Do not know how to serialize a BigInt",
+  ]);
+  t.deepEqual(response.data.first, { shared: true });
+  t.deepEqual(response.data.second, { shared: true });
+});
+
+test("Get /json/schema only sends the properties in the response schema", async t => {
+  const response = await Server.get("/json/schema");
+
+  t.deepEqual(response.data, {
+    id: 7,
+    "quoted \"key\"": "escaped",
+    profile: { name: "walker", joined: "2024-01-01T00:00:00.000Z" },
+    tags: [{ label: "a" }, null, { label: "b" }],
+  });
+});
+
+test("Get /prepared sends the prepared status, headers and body", async t => {
+  const response = await Server.get("/prepared");
+
+  t.is(response.status, 202);
+  t.is(response.data, "Prepared");
+  t.is(response.headers['x-prepared'], "one, two");
+  t.is(response.headers['server'], "prepared");
+  t.is(response.headers['content-type'], "text/plain; charset=UTF-8");
+
+  const png = await Server.get("/prepared/png", { responseType: "arraybuffer" });
+  t.is(png.headers['content-type'], "image/png");
+  t.is(Buffer.from(png.data).toString("hex"), "89504e470d0a1a0a0000000d49484452");
+});
+
+test("Get /prepared/invalid rejects invalid options", async t => {
+  const response = await Server.get("/prepared/invalid");
+
+  t.deepEqual(response.data, [
+    "Invalid status code: 42",
+    "Invalid header name: bad name",
+    "Invalid value for header: content-type",
+  ]);
+});
+
+test("Static routes are answered without calling into JS", async t => {
+  const health = await Server.get("/static/health");
+
+  t.deepEqual(health.data, { status: "ok" });
+  t.is(health.headers['content-type'], "application/json");
+  t.is(health.headers['cache-control'], "no-store");
+
+  try {
+    const _ = await Server.post("/static/maintenance", "ignored");
+    t.fail();
+  } catch (error) {
+    t.is(error.response.status, 503);
+    t.is(error.response.data, "Down for maintenance");
+  }
+
+  const routes = Walker.listRoutes();
+  t.true(routes.some((route) => route.method === "GET" && route.path === "/static/health"));
+});
+
+test("serveDir sends files with validators", async t => {
+  const response = await Server.get("/public/");
+
+  t.is(response.data, "<h1>Walker</h1>\n");
+  t.is(response.headers['content-type'], "text/html; charset=UTF-8");
+  t.is(response.headers['accept-ranges'], "bytes");
+  t.truthy(response.headers['last-modified']);
+  t.regex(response.headers['etag'], /^"[0-9a-f]+-[0-9a-f]+"$/);
+
+  const docs = await Server.get("/public/docs");
+  t.is(docs.data, "<h1>Docs</h1>\n");
+
+  const route = await Server.get("/public/route");
+  t.is(route.data, "Route");
+});
+
+test("serveDir answers conditional requests", async t => {
+  const first = await Server.get("/public/style.css");
+  const options = { validateStatus: () => true };
+
+  const etag = await Server.get("/public/style.css", { ...options, headers: { "If-None-Match": first.headers['etag'] } });
+  t.is(etag.status, 304);
+  t.is(etag.data, "");
+
+  const since = await Server.get("/public/style.css", {
+    ...options,
+    headers: { "If-Modified-Since": first.headers['last-modified'] },
+  });
+  t.is(since.status, 304);
+
+  const changed = await Server.get("/public/style.css", { ...options, headers: { "If-None-Match": '"other"' } });
+  t.is(changed.status, 200);
+  t.is(changed.data, "body { color: red; }\n");
+});
+
+test("serveDir supports range requests", async t => {
+  const options = { validateStatus: () => true };
+
+  const partial = await Server.get("/public/app.js", { ...options, headers: { Range: "bytes=0-6" } });
+  t.is(partial.status, 206);
+  t.is(partial.data, "console");
+  t.is(partial.headers['content-range'], "bytes 0-6/23");
+
+  const suffix = await Server.get("/public/app.js", { ...options, headers: { Range: "bytes=-9" } });
+  t.is(suffix.data, '"walker");');
+
+  const outside = await Server.get("/public/app.js", { ...options, headers: { Range: "bytes=100-" } });
+  t.is(outside.status, 416);
+  t.is(outside.headers['content-range'], "bytes */23");
+});
+
+test("serveDir sends precompressed files when accepted", async t => {
+  const compressed = await Server.get("/public/app.js", {
+    decompress: false,
+    responseType: "arraybuffer",
+    headers: { "Accept-Encoding": "gzip" },
+  });
+  t.is(compressed.headers['content-encoding'], "gzip");
+  t.is(compressed.headers['vary'], "accept-encoding");
+
+  const plain = await Server.get("/public/app.js", { headers: { "Accept-Encoding": "identity" } });
+  t.is(plain.headers['content-encoding'], undefined);
+  t.is(plain.headers['content-type'], "text/javascript; charset=UTF-8");
+  t.is(plain.data, 'console.log("walker");\n');
+});
+
+test("serveDir skips precompressed symlinks outside the root", async t => {
+  const response = await Server.get("/linked/app.js", {
+    decompress: false,
+    responseType: "arraybuffer",
+    headers: { "Accept-Encoding": "gzip" },
+  });
+
+  t.is(response.headers['content-encoding'], undefined);
+  t.is(Buffer.from(response.data).toString(), "inside\n");
+});
+
+test("serveDir blocks traversal and dotfiles", async t => {
+  const options = { validateStatus: () => true };
+
+  t.is((await Server.get("/public/.env", options)).status, 404);
+  t.is((await Server.get("/app/.env", options)).status, 403);
+  t.is((await Server.get("/public/docs%2f..%2f..%2findex.spec.mjs", options)).status, 403);
+  t.is((await Server.get("/public/missing.js", options)).status, 404);
+});
+
+test("serveDir applies fallback and cache rules", async t => {
+  const options = { validateStatus: () => true };
+
+  const fallback = await Server.get("/app/some/page", options);
+  t.is(fallback.status, 200);
+  t.is(fallback.data, "<h1>Walker</h1>\n");
+  t.is(fallback.headers['cache-control'], "no-cache");
+
+  const css = await Server.get("/app/style.css", options);
+  t.is(css.headers['cache-control'], "public, max-age=60");
+
+  t.is((await Server.get("/app/missing.js", options)).status, 404);
+});
+
+test("sendFile streams a file as a download", async t => {
+  const response = await Server.get("/file/download");
+
+  t.is(response.data, 'console.log("walker");\n');
+  t.is(response.headers['content-length'], "23");
+  t.is(response.headers['content-type'], "text/plain");
+  t.is(response.headers['x-export'], "yes");
+  t.is(response.headers['content-disposition'], "attachment; filename=\"rapport _.js\"; filename*=UTF-8''rapport%20%C3%A9%2Ejs");
+
+  const partial = await Server.get("/file/download", { headers: { Range: "bytes=8-10" } });
+  t.is(partial.status, 206);
+  t.is(partial.data, "log");
+});
+
+test("sendFile handles disabled ranges and missing files", async t => {
+  const options = { validateStatus: () => true };
+
+  const full = await Server.get("/file/norange", { ...options, headers: { Range: "bytes=0-3" } });
+  t.is(full.status, 200);
+  t.is(full.data, "body { color: red; }\n");
+  t.is(full.headers['accept-ranges'], undefined);
+
+  t.is((await Server.get("/file/missing", options)).status, 404);
+});
+
+test("startStream sends chunks in order with the given status and headers", async t => {
+  const response = await Server.get("/stream/csv");
+
+  t.is(response.status, 201);
+  t.is(response.headers['content-type'], "text/csv");
+  t.is(response.headers['transfer-encoding'], "chunked");
+  t.is(response.data, "id,name\n1,row 1\n2,row 2\n3,row 3\ndone\n");
+});
+
+test("startStream rejects writes once the client disconnects", async t => {
+  await new Promise((resolve, reject) => {
+    const req = http.get("http://0.0.0.0:8080/stream/endless", (rsp) => {
+      rsp.once("data", () => {
+        req.destroy();
+        resolve();
+      });
+    });
+
+    req.on("error", reject);
+  });
+
+  let message = "none";
+  for (let i = 0; i < 50 && message === "none"; i++) {
+    await new Promise((resolve) => setTimeout(resolve, 20));
+    message = (await Server.get("/stream/last-error")).data;
+  }
+
+  t.is(message, "The client disconnected. true");
+});
+
+test("sse frames events and ends when the stream is closed", async t => {
+  const response = await Server.get("/sse/events", { headers: { "Last-Event-ID": "7" } });
+
+  t.is(response.headers['content-type'], "text/event-stream");
+  t.is(response.headers['cache-control'], "no-cache");
+  t.is(response.data, [
+    "retry: 3000\n\n",
+    "id: 1\nevent: greeting\ndata: hello\ndata: world\n\n",
+    "data: {\"count\":2}\n\n",
+    "event: resume\ndata: 7\n\n",
+  ].join(""));
+});
+
+test("sse handlers can send another response instead of the stream", async t => {
+  const denied = await Server.get("/sse/private", { validateStatus: () => true });
+  t.is(denied.status, 401);
+  t.is(denied.data, "Unauthorised");
+
+  const allowed = await Server.get("/sse/private", { headers: { "x-token": "secret" } });
+  t.is(allowed.status, 200);
+  t.is(allowed.data, "");
+});
+
+test("sse requests can be read after the handler awaits", async t => {
+  const [response] = await Promise.all([
+    Server.get("/sse/rooms/lobby?page=2", { headers: { "x-user": "ada" } }),
+    Server.get("/", { headers: { "x-user": "someone else" } }),
+  ]);
+
+  t.is(response.data, 'data: {"room":"lobby","user":"ada","page":"2","late":"Already sent response."}\n\n');
+});
+
+test("sse channels replay missed events and broadcast new ones", async t => {
+  t.is((await Server.get("/sse/publish/1")).data, 0);
+  t.is((await Server.get("/sse/publish/2")).data, 0);
+
+  let received = "";
+  let req;
+
+  await new Promise((resolve, reject) => {
+    req = http.get("http://0.0.0.0:8080/sse/news", { headers: { "Last-Event-ID": "1" } }, (rsp) => {
+      rsp.setEncoding("utf8");
+      rsp.on("data", (chunk) => {
+        received += chunk;
+        if (received.includes("story 2")) {
+          resolve();
+        }
+      });
+    });
+
+    req.on("error", reject);
+  });
+
+  t.is((await Server.get("/sse/publish/3")).data, 1);
+
+  for (let i = 0; i < 50 && !(received.includes("story 3") && received.includes(":\n\n")); i++) {
+    await new Promise((resolve) => setTimeout(resolve, 20));
+  }
+
+  req.destroy();
+
+  t.false(received.includes("story 1"));
+  t.true(received.includes("id: 2\nevent: news\ndata: story 2\n\n"));
+  t.true(received.includes("id: 3\nevent: news\ndata: story 3\n\n"));
+  t.true(received.includes(":\n\n"));
+});
+
+test("sse channels disconnect subscribers which stop reading", async t => {
+  let closed = false;
+  let req;
+
+  await new Promise((resolve, reject) => {
+    req = http.get("http://0.0.0.0:8080/sse/flood", (rsp) => {
+      rsp.pause();
+      rsp.socket.on("close", () => closed = true);
+      resolve();
+    });
+
+    req.on("error", reject);
+  });
+
+  let count = (await Server.get("/sse/flood/publish")).data;
+  t.is(count, 1);
+
+  // The socket buffers fill up first, after that events queue on the server until the limit
+  for (let i = 0; i < 1_000 && count !== 0; i++) {
+    count = (await Server.get("/sse/flood/publish")).data;
+  }
+
+  t.is(count, 0);
+
+  for (let i = 0; i < 50 && !closed; i++) {
+    await new Promise((resolve) => setTimeout(resolve, 20));
+  }
+
+  t.true(closed);
+  req.destroy();
+});
+
+const wsLastClose = async () => {
+  let message = "none";
+  for (let i = 0; i < 50 && message === "none"; i++) {
+    await new Promise((resolve) => setTimeout(resolve, 20));
+    message = (await Server.get("/ws/last-close")).data;
+  }
+
+  return message;
+};
+
+test.serial("ws handlers can refuse the upgrade", async t => {
+  const refused = await connect("/ws/chat");
+  t.true(refused.refused);
+  t.is(refused.status, 401);
+  t.is(refused.body, "Unauthorised");
+});
+
+test.serial("ws echoes text and binary messages and answers pings", async t => {
+  const socket = await connect("/ws/chat", { "x-token": "secret" });
+  t.is(await socket.nextText(), "welcome lobby");
+
+  socket.sendText("hello");
+  t.is(await socket.nextText(), "echo: hello");
+
+  socket.send(OPCODES.binary, [1, 2, 3]);
+  const binary = await socket.next();
+  t.is(binary.opcode, OPCODES.binary);
+  t.deepEqual([...binary.payload], [3, 2, 1]);
+
+  socket.send(OPCODES.ping, "are you there");
+  const pong = await socket.next();
+  t.is(pong.opcode, OPCODES.pong);
+  t.is(pong.payload.toString(), "are you there");
+
+  socket.close(1000, "done");
+  const close = await socket.next();
+  t.is(close.opcode, OPCODES.close);
+  t.is(closeReason(close).code, 1000);
+  t.is(await socket.next(), null);
+
+  t.is(await wsLastClose(), "1000 done true");
+});
+
+test.serial("ws servers can close sockets with a code", async t => {
+  const socket = await connect("/ws/chat", { "x-token": "secret" });
+  await socket.next();
+
+  socket.sendText("bye");
+  const close = await socket.next();
+  t.deepEqual(closeReason(close), { code: 4000, reason: "see you" });
+  socket.destroy();
+
+  t.is(await wsLastClose(), "4000 see you true");
+});
+
+test.serial("ws closes sockets sending messages over the limit", async t => {
+  const socket = await connect("/ws/chat", { "x-token": "secret" });
+  await socket.next();
+
+  socket.sendText("x".repeat(2048));
+  const close = await socket.next();
+  t.deepEqual(closeReason(close), { code: 1009, reason: "Message too big" });
+  socket.destroy();
+
+  t.is(await wsLastClose(), "1009 Message too big true");
+});
+
+test.serial("ws broadcasts to rooms and leaves them on close", async t => {
+  const first = await connect("/ws/chat", { "x-token": "secret" });
+  const second = await connect("/ws/chat", { "x-token": "secret" });
+  await first.next();
+  await second.next();
+
+  t.is((await Server.get("/ws/lobby-size")).data, 2);
+
+  first.sendText("all:hi everyone");
+  t.is(await second.nextText(), "hi everyone");
+
+  first.sendText("ping");
+  t.is(await first.nextText(), "echo: ping");
+
+  first.destroy();
+  second.destroy();
+
+  let size = 2;
+  for (let i = 0; i < 50 && size !== 0; i++) {
+    await new Promise((resolve) => setTimeout(resolve, 20));
+    size = (await Server.get("/ws/lobby-size")).data;
+  }
+
+  t.is(size, 0);
+});
+
+test.serial("ws drops sockets which stop reading", async t => {
+  const socket = await connect("/ws/flood");
+  socket.socket.pause();
+
+  let count = (await Server.get("/ws/flood/publish")).data;
+  t.is(count, 1);
+
+  for (let i = 0; i < 1_000 && count !== 0; i++) {
+    count = (await Server.get("/ws/flood/publish")).data;
+  }
+
+  t.is(count, 0);
+  t.is(await wsLastClose(), "1013 Send buffer full true");
+  socket.destroy();
+});
+
+test("Get /bytes sends binary data with a content type", async t => {
+  const sniffed = await Server.get("/bytes/sniffed", { responseType: "arraybuffer" });
+  t.is(sniffed.headers['content-type'], "image/png");
+  t.is(Buffer.from(sniffed.data).toString("hex"), "89504e470d0a1a0a0000000d49484452");
+
+  const typed = await Server.get("/bytes/typed", { responseType: "arraybuffer" });
+  t.is(typed.headers['content-type'], "application/x-protobuf");
+  t.deepEqual([...Buffer.from(typed.data)], [8, 1, 16, 2]);
+
+  const unknown = await Server.get("/bytes/unknown", { responseType: "arraybuffer" });
+  t.is(unknown.headers['content-type'], "application/octet-stream");
+
+  const bm = await Server.get("/bytes/bm", { responseType: "arraybuffer" });
+  t.is(bm.headers['content-type'], "application/octet-stream");
+});
+
+test("Get /buffer/large sends a large buffer intact", async t => {
+  for (let i = 0; i < 3; i++) {
+    const response = await Server.get("/buffer/large", { responseType: "arraybuffer" });
+    const data = Buffer.from(response.data);
+
+    t.is(data.length, 64 * 1024);
+    t.true(data.every((byte) => byte === 7));
+  }
+});
+
+test("Post /return_text_body echoes a large body", async t => {
+  const body = "walker ".repeat(20_000);
+  const response = await Server.post("/return_text_body", body, { headers: { 'content-type': 'text/plain' } });
+
+  t.is(response.data, body);
+});
+
+test("Get /buffer/reused copies arrays which JS changes after sending", async t => {
+  const response = await Server.get("/buffer/reused", { responseType: "arraybuffer" });
+  const data = Buffer.from(response.data);
+
+  t.is(data.length, 64 * 1024);
+  t.true(data.every((byte) => byte === 1));
+});
+
+test("Get /buffer/no-copy sends a large array without copying", async t => {
+  for (let i = 0; i < 3; i++) {
+    const response = await Server.get("/buffer/no-copy", { responseType: "arraybuffer" });
+    const data = Buffer.from(response.data);
+
+    t.is(data.length, 64 * 1024);
+    t.true(data.every((byte) => byte === 3));
+  }
+});
+
+test("Post /body_no_copy hands the body over to JS", async t => {
+  const response = await Server.post("/body_no_copy", "shared body", { headers: { 'content-type': 'text/plain' } });
+
+  t.deepEqual(response.data, { shared: "shared body", after: 0 });
+});
+
+test("Post /body_twice reads the body more than once", async t => {
+  const response = await Server.post("/body_twice", "x".repeat(10_000), { headers: { 'content-type': 'text/plain' } });
+
+  t.deepEqual(response.data, { length: 10_000, same: true });
+});
+
+test("Post bodies sent with a Content-Encoding are decoded", async t => {
+  const text = "walker ".repeat(5_000);
+  const gzip = await Server.post("/return_text_body", zlib.gzipSync(text), {
+    headers: { 'content-type': 'text/plain', 'content-encoding': 'gzip' },
+  });
+  t.is(gzip.data, text);
+
+  const sent = { items: Array.from({ length: 50 }, (_, id) => ({ id })) };
+  const brotli = await Server.post("/json_body", zlib.brotliCompressSync(JSON.stringify(sent)), {
+    headers: { 'content-type': 'application/json', 'content-encoding': 'br' },
+  });
+  t.deepEqual(brotli.data, sent);
+});
+
+test("Post bodies which decode past the limit are rejected", async t => {
+  const bomb = zlib.gzipSync(Buffer.alloc(4 * 1024 * 1024));
+  const response = await Server.post("/return_text_body", bomb, {
+    headers: { 'content-type': 'text/plain', 'content-encoding': 'gzip' },
+    validateStatus: () => true,
+  });
+
+  t.true(bomb.length < 262_144);
+  t.is(response.status, 413);
+});
+
+test("Post bodies with unsupported or broken encodings are rejected", async t => {
+  const unsupported = await Server.post("/return_text_body", "testing", {
+    headers: { 'content-type': 'text/plain', 'content-encoding': 'compress' },
+    validateStatus: () => true,
+  });
+  t.is(unsupported.status, 415);
+  t.deepEqual(unsupported.data, { errors: ["body: unsupported content encoding compress"] });
+
+  const broken = await Server.post("/return_text_body", "not gzip at all", {
+    headers: { 'content-type': 'text/plain', 'content-encoding': 'gzip' },
+    validateStatus: () => true,
+  });
+  t.is(broken.status, 400);
+  t.deepEqual(broken.data, { errors: ["body: invalid compressed data"] });
+});
+
 test("Get /json returns json", async t => {
   const response = await Server.get("/json");
   const json = response.data;
@@ -185,4 +848,150 @@ test("Post /return_text_body returns text body", async t => {
   const response = await Server.post("/return_text_body", "testing");
 
   t.is(response.data, "testing");
-});
\ No newline at end of file
+});
+
+test("listRoutes includes registered routes", t => {
+  const routes = Walker.listRoutes();
+
+  const hello = routes.find((route) => route.path === "/hello/:name");
+  t.is(hello.method, "GET");
+
+  const named = routes.find((route) => route.path === "/named");
+  t.is(named.name, "named");
+  t.is(named.options.name, "named");
+
+  const post = routes.find((route) => route.path === "/return_text_body");
+  t.is(post.method, "POST");
+});
+
+test("openApiDocument describes registered routes", t => {
+  const document = Walker.openApiDocument("Test", "2.0.0");
+
+  t.is(document.openapi, "3.1.0");
+  t.is(document.info.title, "Test");
+
+  const operation = document.paths["/users/{id}"].get;
+  t.is(operation.operationId, "getUser");
+  t.deepEqual(operation.parameters[0], { name: "id", in: "path", required: true, schema: { type: "integer" } });
+  t.is(operation.parameters[1].in, "query");
+  t.is(operation.responses["200"].content["application/json"].schema.type, "object");
+
+  t.truthy(document.paths["/hello/{name}"].get);
+});
+
+test("Get /users/:id validates params", async t => {
+  const response = await Server.get("/users/12");
+  t.deepEqual(response.data, { id: "12" });
+
+  try {
+    const _ = await Server.get("/users/abc");
+    t.fail();
+  } catch (error) {
+    t.is(error.response.status, 400);
+    t.true(error.response.data.errors[0].startsWith("params/id"));
+  }
+});
+
+test("Post /validated rejects invalid bodies", async t => {
+  const response = await Server.post("/validated", { name: "walker" });
+  t.deepEqual(response.data, { name: "walker" });
+
+  try {
+    const _ = await Server.post("/validated", { name: 5 });
+    t.fail();
+  } catch (error) {
+    t.is(error.response.status, 400);
+    t.is(error.response.data.errors.length, 1);
+  }
+});
+
+test("Post /json_body returns the parsed body", async t => {
+  const sent = { nested: { list: [1, 2.5, "three", null, true] } };
+  const response = await Server.post("/json_body", sent);
+
+  t.deepEqual(response.data, sent);
+});
+
+test("Post /json_body rejects malformed JSON", async t => {
+  try {
+    const _ = await Server.post("/json_body", "{ broken", { headers: { 'content-type': 'application/json' } });
+    t.fail();
+  } catch (error) {
+    t.is(error.response.status, 400);
+  }
+});
+
+test("Post /json_lazy tells wrong content types apart from malformed JSON", async t => {
+  const wrongType = await Server.post("/json_lazy", '{"a":1}', {
+    headers: { 'content-type': 'text/plain' },
+    validateStatus: () => true,
+  });
+  t.is(wrongType.data, "Expected an application/json content type.");
+
+  const malformed = await Server.post("/json_lazy", '{"a":', {
+    headers: { 'content-type': 'application/json' },
+    validateStatus: () => true,
+  });
+  t.true(malformed.data.startsWith("Malformed JSON body: "));
+});
+
+test("Post /form decodes urlencoded bodies", async t => {
+  const form = new URLSearchParams();
+  form.append("name", "walker server");
+  form.append("tag", "a&b");
+  form.append("tag", "c=d");
+
+  const response = await Server.post("/form", form);
+
+  t.deepEqual(response.data, { name: "walker server", tag: ["a&b", "c=d"] });
+});
+
+test("Post /multipart returns fields and files", async t => {
+  const form = new FormData();
+  form.append("field", "value");
+  form.append("upload", new Blob(["file contents"], { type: "text/plain" }), "notes.txt");
+
+  const response = await Server.post("/multipart", form);
+
+  t.deepEqual(response.data.fields, [{ name: "field", value: "value" }]);
+  t.deepEqual(response.data.files, [{ name: "upload", filename: "notes.txt", size: 13, text: "file contents" }]);
+});
+
+test("Post /multipart rejects parts over the limit", async t => {
+  const form = new FormData();
+  form.append("field", "x".repeat(2048));
+
+  try {
+    const _ = await Server.post("/multipart", form);
+    t.fail();
+  } catch (error) {
+    t.is(error.response.status, 413);
+  }
+});
+
+test("Post /multipart/upload allows spilled files past the in-memory part limit", async t => {
+  const form = new FormData();
+  form.append("upload", new Blob(["x".repeat(4096)]), "upload.txt");
+
+  const response = await Server.post("/multipart/upload", form);
+  t.deepEqual(response.data, [{ name: "upload", size: 4096 }]);
+
+  const tooLarge = new FormData();
+  tooLarge.append("upload", new Blob(["x".repeat(16384)]), "upload.txt");
+  const rejected = await Server.post("/multipart/upload", tooLarge, { validateStatus: () => true });
+  t.is(rejected.status, 413);
+
+  const field = new FormData();
+  field.append("field", "x".repeat(2048));
+  const fieldRejected = await Server.post("/multipart/upload", field, { validateStatus: () => true });
+  t.is(fieldRejected.status, 413);
+});
+
+test("Post /multipart/spilled writes private files which are removed after the response", async t => {
+  const form = new FormData();
+  form.append("upload", new Blob(["x".repeat(4096)]), "upload.txt");
+
+  const response = await Server.post("/multipart/spilled", form);
+  t.is(response.data.mode, 0o600);
+  t.false(fs.existsSync(response.data.path));
+});
@@ -0,0 +1,130 @@
+import test from 'ava'
+import axios from 'axios';
+import net from 'net';
+
+const Server = axios.create({
+    baseURL: 'http://127.0.0.1:8080/'
+  });
+
+import * as Walker from '../index.js'
+
+const config = {
+    url: "0.0.0.0:8080",
+    worker_threads: "1",
+    trusted_proxies: "127.0.0.0/8, ::1, 10.0.0.0/8",
+    proxy_protocol_url: "0.0.0.0:8081"
+}
+
+// Sends a raw request to the PROXY protocol listener and returns the parsed JSON body
+const sendProxied = (header) => new Promise((resolve, reject) => {
+    const socket = net.connect(8081, "127.0.0.1", () => {
+        socket.write(Buffer.concat([
+            header,
+            Buffer.from("GET /client HTTP/1.1\r\nHost: proxied.example.com\r\nConnection: close\r\n\r\n"),
+        ]));
+    });
+
+    const chunks = [];
+    socket.on("data", (chunk) => chunks.push(chunk));
+    socket.on("error", reject);
+    socket.on("end", () => {
+        const raw = Buffer.concat(chunks).toString();
+        const body = raw.slice(raw.indexOf("\r\n\r\n") + 4);
+        resolve(JSON.parse(body));
+    });
+});
+
+test.serial.before(async (_) => {
+    Walker.get("/client", (res) => {
+        res.sendObject({
+            ip: res.getClientIp(),
+            scheme: res.getClientScheme(),
+            host: res.getClientHost(),
+            url: res.getUrl(),
+            peer: res.getPeerAddress(),
+            destination: res.getDestinationAddress(),
+        });
+    });
+
+    Walker.startWithConfig(config);
+
+    // Sleeep for 100ms to let server start
+    await new Promise((resolve) => setTimeout(resolve, 100));
+});
+
+test("X-Forwarded headers are read from trusted proxies", async t => {
+    const headers = {
+        'x-forwarded-for': '198.51.100.7, 203.0.113.5, 10.0.0.2',
+        'x-forwarded-proto': 'https',
+        'x-forwarded-host': 'example.com',
+    };
+
+    const response = await Server.get("/client?page=2", { headers });
+
+    t.is(response.data.ip, "203.0.113.5");
+    t.is(response.data.scheme, "https");
+    t.is(response.data.host, "example.com");
+    t.is(response.data.url, "https://example.com/client?page=2");
+});
+
+test("X-Forwarded lists of a different length use the value from the nearest proxy", async t => {
+    const headers = {
+        'x-forwarded-for': '203.0.113.5, 10.0.0.2',
+        'x-forwarded-proto': 'http, https, https',
+        'x-forwarded-host': 'evil.example, example.com, example.com',
+    };
+
+    const response = await Server.get("/client", { headers });
+
+    t.is(response.data.ip, "203.0.113.5");
+    t.is(response.data.scheme, "https");
+    t.is(response.data.host, "example.com");
+
+    const hostOnly = await Server.get("/client", { headers: { 'x-forwarded-host': 'evil.example, example.com' } });
+    t.is(hostOnly.data.host, "example.com");
+});
+
+test("Forwarded header takes priority over X-Forwarded headers", async t => {
+    const headers = {
+        'forwarded': 'for="[2001:db8:cafe::17]:4711";proto=https;host=api.example.com, for=10.0.0.3',
+        'x-forwarded-for': '203.0.113.5',
+    };
+
+    const response = await Server.get("/client", { headers });
+
+    t.is(response.data.ip, "2001:db8:cafe::17");
+    t.is(response.data.scheme, "https");
+    t.is(response.data.host, "api.example.com");
+});
+
+test("Without forwarding headers the peer is the client", async t => {
+    const response = await Server.get("/client");
+
+    t.is(response.data.ip, "127.0.0.1");
+    t.is(response.data.scheme, "http");
+    t.is(response.data.host, "127.0.0.1:8080");
+});
+
+test("PROXY protocol v1 headers set the peer and destination", async t => {
+    const header = Buffer.from("PROXY TCP4 192.0.2.10 192.0.2.20 56324 443\r\n");
+    const data = await sendProxied(header);
+
+    t.is(data.peer, "192.0.2.10:56324");
+    t.is(data.destination, "192.0.2.20:443");
+    t.is(data.ip, "192.0.2.10");
+});
+
+test("PROXY protocol v2 headers set the peer and destination", async t => {
+    const header = Buffer.from([
+        0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A,
+        0x21, 0x11, 0x00, 0x0C,
+        198, 51, 100, 4,
+        198, 51, 100, 9,
+        0x1F, 0x90,
+        0x01, 0xBB,
+    ]);
+    const data = await sendProxied(header);
+
+    t.is(data.peer, "198.51.100.4:8080");
+    t.is(data.destination, "198.51.100.9:443");
+});
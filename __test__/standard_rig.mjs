@@ -1,5 +1,32 @@
+import fs from 'node:fs';
+import os from 'node:os';
+import path from 'node:path';
+import { fileURLToPath } from 'node:url';
+import zlib from 'node:zlib';
+
 import * as Walker from '../index.js'
 
+const PUBLIC_DIR = fileURLToPath(new URL('./public', import.meta.url));
+
+/** A mount whose precompressed file is a symlink to a file outside of it */
+const makeLinkedDir = () => {
+    const base = fs.mkdtempSync(path.join(os.tmpdir(), "walker-"));
+    const root = path.join(base, "root");
+
+    fs.mkdirSync(root);
+    fs.writeFileSync(path.join(root, "app.js"), "inside\n");
+    fs.writeFileSync(path.join(base, "secret.js.gz"), zlib.gzipSync("outside\n"));
+    fs.symlinkSync(path.join(base, "secret.js.gz"), path.join(root, "app.js.gz"));
+
+    return root;
+};
+
+let lastStreamError = null;
+const newsChannel = Walker.createSseChannel({ history: 10 });
+const floodChannel = Walker.createSseChannel({ maxBuffered: 256 * 1024 });
+const FLOOD_EVENT = "x".repeat(64 * 1024);
+let lastWsClose = "none";
+
 const registerRoutes = () => {
 
     Walker.get("/", (res) => {
@@ -42,6 +69,103 @@ const registerRoutes = () => {
         res.sendObject(headers);
     });
 
+    Walker.get("/nestedParams", (res) => {
+        res.sendObject({
+            raw: res.getQueryString(),
+            params: res.getQueryParams(true),
+        });
+    });
+
+    Walker.put("/requestInfo/:id", (res) => {
+        res.sendObject({
+            method: res.getMethod(),
+            path: res.getPath(),
+            url: res.getUrl(),
+            version: res.getHttpVersion(),
+            scheme: res.getScheme(),
+            peerAddress: res.getPeerAddress(),
+            peerIp: res.getPeerIp(),
+            tags: res.getHeaderValues("x-tag"),
+            clientIp: res.getClientIp(),
+            clientScheme: res.getClientScheme(),
+        });
+    });
+
+    Walker.get("/cookies", (res) => {
+        res.setCookie("first", "one", { path: "/", httpOnly: true });
+        res.setCookie("second", "two words", { maxAge: 60, sameSite: "Lax", secure: true });
+        res.sendObject({
+            all: res.getCookies(),
+            session: res.getCookie("session"),
+        });
+    });
+
+    Walker.get("/responseHeaders", (res) => {
+        res.appendHeader("Link", "</a>; rel=preload");
+        res.appendHeader("link", "</b>; rel=preload");
+        res.setHeader("x-replaced", "first");
+        res.setHeader("X-Replaced", "second");
+        res.setHeaders({ vary: ["Accept", "Origin"], "content-type": "text/csv" });
+        res.appendHeader("x-removed", "gone");
+        res.removeHeader("x-removed");
+        res.removeHeader("server");
+
+        const pending = res.getResponseHeaders();
+        res.sendText(JSON.stringify(pending));
+    });
+
+    Walker.get("/invalidHeader", (res) => {
+        const errors = [];
+        const attempts = [
+            () => res.addHeader("x-split", "value\r\nset-cookie: injected=1"),
+            () => res.setHeader("bad name", "value"),
+            () => res.setHeaders({ "x-fine": "ok", "x-bad": "a\nb" }),
+        ];
+
+        for (const attempt of attempts) {
+            try {
+                attempt();
+            } catch (error) {
+                errors.push(error.message);
+            }
+        }
+
+        res.sendObject({ errors, pending: res.getResponseHeaders() });
+    });
+
+    const png = Buffer.from("89504e470d0a1a0a0000000d49484452", "hex");
+    const large = Buffer.alloc(64 * 1024, 7);
+
+    Walker.get("/bytes/sniffed", (res) => {
+        res.sendBytes(png);
+    });
+
+    Walker.get("/bytes/typed", (res) => {
+        res.sendBytes(new Uint8Array([8, 1, 16, 2]), "application/x-protobuf");
+    });
+
+    Walker.get("/bytes/unknown", (res) => {
+        res.sendBytes(new Uint8Array([1, 2, 3]));
+    });
+
+    Walker.get("/bytes/bm", (res) => {
+        res.sendBytes(Buffer.from("BM is not enough to be a bitmap"));
+    });
+
+    Walker.get("/buffer/large", (res) => {
+        res.sendBuffer(large, "application/octet-stream");
+    });
+
+    Walker.get("/buffer/reused", (res) => {
+        const pooled = Buffer.alloc(64 * 1024, 1);
+        res.sendBytes(pooled, "application/octet-stream");
+        pooled.fill(2);
+    });
+
+    Walker.get("/buffer/no-copy", (res) => {
+        res.sendBytesNoCopy(Buffer.alloc(64 * 1024, 3), "application/octet-stream");
+    });
+
     Walker.get("/json", (res) => {
         res.sendObject({
             hello: "world",
@@ -49,6 +173,303 @@ const registerRoutes = () => {
         });
     });
 
+    Walker.get("/json/nested", (res) => {
+        res.sendObject({
+            text: "quote \" slash \\ line\n tab\t \u0001 ünï",
+            numbers: [1, -2, 1.5, 1e21, 2 ** 53, NaN, Infinity],
+            nested: { list: [{ deep: [true, false, null] }], empty: {}, none: [] },
+            skipped: undefined,
+            fn: () => 1,
+            holes: [undefined, () => 1, Symbol("x")],
+            date: new Date(Date.UTC(2024, 0, 2, 3, 4, 5)),
+            custom: { toJSON: (key) => `custom:${key}` },
+            buffer: Buffer.from("walker"),
+        });
+    });
+
+    Walker.get("/json/inherited", (res) => {
+        const value = Object.create({ inherited: "skipped" });
+        value.own = "kept";
+        value[2] = "index";
+        value[Symbol("symbol")] = "skipped";
+        Object.defineProperty(value, "hidden", { value: "skipped", enumerable: false });
+
+        class Point {
+            constructor() {
+                this.x = 1;
+            }
+        }
+        Point.prototype.label = "skipped";
+
+        res.sendObject({ value, point: new Point() });
+    });
+
+    Walker.get("/json/top", (res) => {
+        res.sendObject([1, "two", { three: 3 }]);
+    });
+
+    Walker.get("/json/errors", (res) => {
+        const circular = { name: "loop" };
+        circular.self = circular;
+
+        const errors = [];
+        for (const value of [circular, { big: 10n }]) {
+            try {
+                res.sendObject(value);
+            } catch (error) {
+                errors.push(error.message);
+            }
+        }
+
+        const shared = { shared: true };
+        res.sendObject({ errors, first: shared, second: shared });
+    });
+
+    Walker.get("/json/schema", (res) => {
+        res.sendObject({
+            id: 7,
+            secret: "hidden",
+            "quoted \"key\"": "escaped",
+            profile: { name: "walker", password: "hidden", joined: new Date(Date.UTC(2024, 0, 1)) },
+            tags: [{ label: "a", extra: 1 }, null, { label: "b" }],
+            missing: undefined,
+        });
+    }, {
+        schema: {
+            responses: {
+                "200": {
+                    description: "A user",
+                    content: {
+                        "application/json": {
+                            schema: {
+                                type: "object",
+                                properties: {
+                                    id: { type: "integer" },
+                                    "quoted \"key\"": { type: "string" },
+                                    profile: {
+                                        type: "object",
+                                        properties: { name: { type: "string" }, joined: { type: "string" } },
+                                    },
+                                    tags: {
+                                        type: "array",
+                                        items: { type: ["object", "null"], properties: { label: { type: "string" } } },
+                                    },
+                                    missing: { type: "string" },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        },
+    });
+
+    const prepared = Walker.prepareResponse({
+        body: "Prepared",
+        status: 202,
+        headers: { "x-prepared": ["one", "two"], server: "prepared" },
+    });
+    const preparedPng = Walker.prepareResponse({ body: png });
+
+    Walker.get("/prepared", (res) => {
+        res.setStatusCode(500);
+        res.sendPrepared(prepared);
+    });
+
+    Walker.get("/prepared/png", (res) => {
+        res.sendPrepared(preparedPng);
+    });
+
+    Walker.get("/prepared/invalid", (res) => {
+        const errors = [];
+        const attempts = [
+            { body: "", status: 42 },
+            { body: "", headers: { "bad name": "value" } },
+            { body: "", contentType: "text/plain\n" },
+        ];
+
+        for (const attempt of attempts) {
+            try {
+                Walker.prepareResponse(attempt);
+            } catch (error) {
+                errors.push(error.message);
+            }
+        }
+
+        res.sendObject(errors);
+    });
+
+    Walker.staticRoute(Walker.Methods.GET, "/static/health", {
+        body: JSON.stringify({ status: "ok" }),
+        contentType: "application/json",
+        headers: { "cache-control": "no-store" },
+    });
+
+    Walker.staticRoute(Walker.Methods.POST, "/static/maintenance", { body: "Down for maintenance", status: 503 });
+
+    Walker.serveDir("/public", PUBLIC_DIR);
+
+    Walker.serveDir("/app/", PUBLIC_DIR, {
+        fallback: "index.html",
+        cacheControl: "public, max-age=60",
+        cacheControlByExtension: { ".html": "no-cache" },
+        dotfiles: "deny",
+    });
+
+    Walker.serveDir("/linked", makeLinkedDir());
+
+    Walker.get("/public/route", (res) => {
+        res.sendText("Route");
+    });
+
+    Walker.get("/file/download", (res) => {
+        res.setHeader("x-export", "yes");
+        res.sendFile(`${PUBLIC_DIR}/app.js`, { downloadName: "rapport é.js", contentType: "text/plain" });
+    });
+
+    Walker.get("/file/norange", (res) => {
+        res.sendFile(`${PUBLIC_DIR}/style.css`, { range: false });
+    });
+
+    Walker.get("/file/missing", (res) => {
+        res.sendFile(`${PUBLIC_DIR}/missing.txt`);
+    });
+
+    Walker.get("/stream/csv", async (res) => {
+        const stream = res.startStream(201, { "content-type": "text/csv" });
+
+        await stream.write("id,name\n");
+        for (let i = 1; i <= 3; i++) {
+            await stream.write(Buffer.from(`${i},row ${i}\n`));
+        }
+
+        stream.end("done\n");
+        stream.end();
+    });
+
+    Walker.get("/stream/endless", async (res) => {
+        const stream = res.startStream();
+        const chunk = Buffer.alloc(16 * 1024, 97);
+
+        try {
+            for (;;) {
+                await stream.write(chunk);
+            }
+        } catch (e) {
+            lastStreamError = `${e.message} ${stream.isClosed()}`;
+        }
+    });
+
+    Walker.get("/stream/last-error", (res) => {
+        res.sendText(lastStreamError ?? "none");
+    });
+
+    Walker.sse("/sse/events", (stream) => {
+        stream.send({ event: "greeting", data: "hello\nworld", id: "1" });
+        stream.send({ data: { count: 2 } });
+        stream.send({ event: "resume", data: stream.lastEventId() ?? "none" });
+        stream.close();
+    }, { retry: 3000 });
+
+    Walker.sse("/sse/private", (stream, req) => {
+        if (req.getHeader("x-token") !== "secret") {
+            req.setStatusCode(401);
+            req.sendText("Unauthorised");
+            return;
+        }
+
+        stream.close();
+    });
+
+    Walker.sse("/sse/rooms/:room", async (stream, req) => {
+        await new Promise((resolve) => setTimeout(resolve, 20));
+
+        let late = "sent";
+        try {
+            req.sendText("too late");
+        } catch (error) {
+            late = error.message;
+        }
+
+        await stream.send({
+            data: {
+                room: req.getUrlParams().room,
+                user: req.getHeader("x-user"),
+                page: req.getQueryParams().page,
+                late,
+            },
+        });
+        stream.close();
+    });
+
+    Walker.sse("/sse/news", (stream) => {
+        newsChannel.subscribe(stream);
+    }, { keepAlive: 50 });
+
+    Walker.sse("/sse/flood", (stream) => {
+        floodChannel.subscribe(stream);
+    }, { keepAlive: 0 });
+
+    Walker.get("/sse/flood/publish", (res) => {
+        res.sendText(`${floodChannel.send({ data: FLOOD_EVENT })}`);
+    });
+
+    Walker.get("/sse/publish/:id", (res) => {
+        const { id } = res.getUrlParams();
+        const count = newsChannel.send({ id, event: "news", data: `story ${id}` });
+
+        res.sendText(`${count}`);
+    });
+
+    Walker.ws("/ws/chat", {
+        onOpen: (socket, req) => {
+            if (req.getHeader("x-token") !== "secret") {
+                req.setStatusCode(401);
+                req.sendText("Unauthorised");
+                return;
+            }
+
+            socket.join("lobby");
+            socket.send(`welcome ${socket.rooms().join(",")}`);
+        },
+        onMessage: (socket, message) => {
+            if (typeof message !== "string") {
+                socket.send(Buffer.from(message).reverse());
+            } else if (message === "bye") {
+                socket.close(4000, "see you");
+            } else if (message.startsWith("all:")) {
+                Walker.wsBroadcast("lobby", message.slice(4), socket.id());
+            } else {
+                socket.send(`echo: ${message}`);
+            }
+        },
+        onClose: (socket, code, reason) => {
+            lastWsClose = `${code} ${reason} ${socket.isClosed()}`;
+        },
+    }, { maxMessageSize: 1024 });
+
+    Walker.ws("/ws/flood", {
+        onOpen: (socket) => {
+            socket.join("flood");
+        },
+        onClose: (socket, code, reason) => {
+            lastWsClose = `${code} ${reason} ${socket.isClosed()}`;
+        },
+    }, { maxBufferedAmount: 256 * 1024 });
+
+    Walker.get("/ws/flood/publish", (res) => {
+        res.sendText(`${Walker.wsBroadcast("flood", Buffer.alloc(64 * 1024))}`);
+    });
+
+    Walker.get("/ws/last-close", (res) => {
+        res.sendText(lastWsClose);
+        lastWsClose = "none";
+    });
+
+    Walker.get("/ws/lobby-size", (res) => {
+        res.sendText(`${Walker.wsRoomSize("lobby")}`);
+    });
+
     Walker.get("/fastJson", (res) => {
         res.sendFastObject({
             hello: "world",
@@ -94,6 +515,85 @@ const registerRoutes = () => {
     Walker.post("/return_text_body", (res) => {
         res.sendBytesText(res.getBody());
     });
+
+    Walker.post("/body_no_copy", (res) => {
+        const shared = res.getBodyNoCopy();
+        const after = res.getBody();
+
+        res.sendObject({ shared: Buffer.from(shared).toString(), after: after.length });
+    });
+
+    Walker.post("/body_twice", (res) => {
+        const first = res.getBody();
+        const second = res.getBody();
+
+        res.sendObject({
+            length: first.length,
+            same: Buffer.compare(Buffer.from(first), Buffer.from(second)) === 0,
+        });
+    });
+
+    Walker.get("/named", (res) => {
+        res.sendText("Named route");
+    }, { name: "named" });
+
+    Walker.get("/users/:id", (res) => {
+        res.sendObject({ id: res.getUrlParams().id });
+    }, {
+        name: "getUser",
+        schema: {
+            params: { type: "object", properties: { id: { type: "integer" } } },
+            query: { type: "object", properties: { verbose: { type: "boolean" } } },
+            responses: { "200": { type: "object", properties: { id: { type: "string" } } } },
+        },
+    });
+
+    Walker.post("/validated", (res) => {
+        res.sendBytesText(res.getBody());
+    }, {
+        schema: {
+            body: { type: "object", required: ["name"], properties: { name: { type: "string" } } },
+        },
+    });
+
+    Walker.post("/json_body", (res) => {
+        res.sendObject(res.getJsonBody());
+    }, { jsonBody: true });
+
+    Walker.post("/json_lazy", (res) => {
+        try {
+            res.sendObject(res.getJsonBody());
+        } catch (e) {
+            res.setStatusCode(400);
+            res.sendText(e.message);
+        }
+    });
+
+    Walker.post("/form", (res) => {
+        res.sendObject(res.getFormData());
+    });
+
+    Walker.post("/multipart", (res) => {
+        const body = res.getMultipart();
+        const files = body.files.map((file) => ({
+            name: file.name,
+            filename: file.filename,
+            size: file.size,
+            text: Buffer.from(file.data).toString(),
+        }));
+
+        res.sendObject({ fields: body.fields, files });
+    }, { multipart: { maxPartSize: 1024 } });
+
+    Walker.post("/multipart/upload", (res) => {
+        const body = res.getMultipart();
+        res.sendObject(body.files.map((file) => ({ name: file.name, size: file.size })));
+    }, { multipart: { maxPartSize: 1024, fileMemoryLimit: 512, maxFileSize: 8192 } });
+
+    Walker.post("/multipart/spilled", (res) => {
+        const [file] = res.getMultipart().files;
+        res.sendObject({ path: file.path, mode: fs.statSync(file.path).mode & 0o777 });
+    }, { multipart: { fileMemoryLimit: 512 } });
 };
 
 export default registerRoutes;
\ No newline at end of file
@@ -0,0 +1,141 @@
+import crypto from 'node:crypto';
+import http from 'node:http';
+
+export const OPCODES = { text: 1, binary: 2, close: 8, ping: 9, pong: 10 };
+
+/**
+ * A bare WebSocket client for the tests, Node 20 doesn't ship a stable one.
+ * Resolves with a socket once upgraded or with the response if the upgrade was refused
+ */
+export const connect = (path, headers = {}) => new Promise((resolve, reject) => {
+    const req = http.request({
+        host: "0.0.0.0",
+        port: 8080,
+        path,
+        headers: {
+            Connection: "Upgrade",
+            Upgrade: "websocket",
+            "Sec-WebSocket-Key": crypto.randomBytes(16).toString("base64"),
+            "Sec-WebSocket-Version": "13",
+            ...headers,
+        },
+    });
+
+    req.on("upgrade", (_, socket, head) => resolve(new TestSocket(socket, head)));
+    req.on("response", (rsp) => {
+        let body = "";
+        rsp.setEncoding("utf8");
+        rsp.on("data", (chunk) => body += chunk);
+        rsp.on("end", () => resolve({ refused: true, status: rsp.statusCode, body }));
+    });
+    req.on("error", reject);
+    req.end();
+});
+
+class TestSocket {
+    constructor(socket, head) {
+        this.socket = socket;
+        this.buffer = head;
+        this.frames = [];
+        this.waiting = [];
+        this.closed = false;
+
+        socket.on("data", (chunk) => {
+            this.buffer = Buffer.concat([this.buffer, chunk]);
+            this.parse();
+        });
+        socket.on("close", () => {
+            this.closed = true;
+            this.waiting.splice(0).forEach((resolve) => resolve(null));
+        });
+        this.parse();
+    }
+
+    parse() {
+        while (this.buffer.length >= 2) {
+            let length = this.buffer[1] & 0x7f;
+            let offset = 2;
+
+            if (length === 126) {
+                if (this.buffer.length < 4) return;
+                length = this.buffer.readUInt16BE(2);
+                offset = 4;
+            } else if (length === 127) {
+                if (this.buffer.length < 10) return;
+                length = Number(this.buffer.readBigUInt64BE(2));
+                offset = 10;
+            }
+
+            if (this.buffer.length < offset + length) return;
+
+            const frame = { opcode: this.buffer[0] & 0x0f, payload: this.buffer.subarray(offset, offset + length) };
+            this.buffer = this.buffer.subarray(offset + length);
+
+            const resolve = this.waiting.shift();
+            if (resolve) {
+                resolve(frame);
+            } else {
+                this.frames.push(frame);
+            }
+        }
+    }
+
+    /** The next frame from the server, null once the connection is gone */
+    next() {
+        if (this.frames.length > 0) {
+            return Promise.resolve(this.frames.shift());
+        }
+
+        if (this.closed) {
+            return Promise.resolve(null);
+        }
+
+        return new Promise((resolve) => this.waiting.push(resolve));
+    }
+
+    async nextText() {
+        const frame = await this.next();
+        return frame?.payload.toString();
+    }
+
+    /** Client frames have to be masked */
+    send(opcode, payload) {
+        const data = Buffer.from(payload);
+        const mask = crypto.randomBytes(4);
+
+        let header;
+        if (data.length < 126) {
+            header = Buffer.from([0x80 | opcode, 0x80 | data.length]);
+        } else {
+            header = Buffer.alloc(4);
+            header[0] = 0x80 | opcode;
+            header[1] = 0x80 | 126;
+            header.writeUInt16BE(data.length, 2);
+        }
+
+        for (let i = 0; i < data.length; i++) {
+            data[i] ^= mask[i % 4];
+        }
+
+        this.socket.write(Buffer.concat([header, mask, data]));
+    }
+
+    sendText(text) {
+        this.send(OPCODES.text, text);
+    }
+
+    close(code, reason = "") {
+        const payload = Buffer.alloc(2 + Buffer.byteLength(reason));
+        payload.writeUInt16BE(code, 0);
+        payload.write(reason, 2);
+
+        this.send(OPCODES.close, payload);
+    }
+
+    destroy() {
+        this.socket.destroy();
+    }
+}
+
+/** Reads the code and reason from a close frame */
+export const closeReason = (frame) => ({ code: frame.payload.readUInt16BE(0), reason: frame.payload.subarray(2).toString() });
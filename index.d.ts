@@ -16,32 +16,113 @@ export const enum Methods {
  * Use this to register a new route in the server, the callback function will be called
  * once the endpoint has been hit. The callback includes a RequestBlob which has all the methods
  * needed to get the information from the request
+ * If the options include schemas for the params, query or body the request is validated before
+ * the callback is called, invalid requests are rejected with a 400
+ * A 2xx response schema is compiled into a JSON writer used by `sendObject` for this route
  */
-export function newRoute(route: string, method: Methods, callback: (result: RequestBlob) => void): void
+export function newRoute(route: string, method: Methods, callback: (result: RequestBlob) => void, options?: RouteOptions): void
 /**
  * Adds a handler for the a GET request
  * once the endpoint has been hit. The callback includes a RequestBlob which has all the methods
  * needed to get the information from the request
  */
-export function get(route: string, callback: (result: RequestBlob) => void): void
+export function get(route: string, callback: (result: RequestBlob) => void, options?: RouteOptions): void
 /**
  * Adds a handler for the a POST request
  * once the endpoint has been hit. The callback includes a RequestBlob which has all the methods
  * needed to get the information from the request
  */
-export function post(route: string, callback: (result: RequestBlob) => void): void
+export function post(route: string, callback: (result: RequestBlob) => void, options?: RouteOptions): void
 /**
  * Adds a handler for the a PUT request
  * once the endpoint has been hit. The callback includes a RequestBlob which has all the methods
  * needed to get the information from the request
  */
-export function put(route: string, callback: (result: RequestBlob) => void): void
+export function put(route: string, callback: (result: RequestBlob) => void, options?: RouteOptions): void
 /**
  * Adds a handler for the a PATCH request
  * once the endpoint has been hit. The callback includes a RequestBlob which has all the methods
  * needed to get the information from the request
  */
-export function patch(route: string, callback: (result: RequestBlob) => void): void
+export function patch(route: string, callback: (result: RequestBlob) => void, options?: RouteOptions): void
+/**
+ * Adds a route which always sends the same response, it is answered on the worker thread
+ * without calling into JS so it keeps responding while the JS thread is busy
+ * Throws if the status code or any header is invalid
+ */
+export function staticRoute(method: Methods, route: string, response: PrepareResponseOptions): void
+/**
+ * Returns every route registered so far in the order they were added
+ * Each entry includes the method, the path pattern, the name and the options it was registered with
+ */
+export function listRoutes(): Array<RouteInfo>
+/**
+ * Generates an OpenAPI 3.1 document from the registered routes and the schemas
+ * attached to them when they were registered
+ */
+export function openApiDocument(title?: string, version?: string): any
+/** JSON schemas describing the inputs and outputs of a route */
+export interface RouteSchema {
+  /** An object schema whose properties describe the url parameters */
+  params?: any
+  /** An object schema whose properties describe the query parameters */
+  query?: any
+  /** The schema of the JSON request body */
+  body?: any
+  /**
+   * An object keyed by status code, each value is either a schema or an OpenAPI response object
+   * The first 2xx schema is used to serialize objects sent with `sendObject`, only the properties it lists are sent
+   */
+  responses?: any
+}
+/** Limits for routes which accept multipart/form-data bodies */
+export interface MultipartOptions {
+  /** The largest size in bytes allowed for a single part kept in memory, defaults to 1MB */
+  maxPartSize?: number
+  /** The largest size in bytes allowed for the whole body, defaults to 16MB */
+  maxBodySize?: number
+  /** The largest size in bytes allowed for a file part written to a temporary file, defaults to the max body size */
+  maxFileSize?: number
+  /**
+   * File parts larger than this many bytes are written to a temporary file instead of kept in memory,
+   * the files are removed once the response has been sent or the client disconnects
+   */
+  fileMemoryLimit?: number
+  /** The directory temporary files are written to, defaults to the OS temp directory */
+  tempDir?: string
+}
+/** Optional settings that can be attached to a route when it is registered */
+export interface RouteOptions {
+  /** A name used to identify the route, this is shown when listing routes */
+  name?: string
+  /**
+   * Schemas for the route, these are used when generating the OpenAPI document
+   * and to validate requests before they reach JS
+   */
+  schema?: RouteSchema
+  /**
+   * Parse the body as JSON on the worker thread before calling the handler,
+   * malformed bodies are rejected with a 400 and other content types with a 415
+   */
+  jsonBody?: boolean
+  /**
+   * Stream multipart/form-data bodies into parts on the worker thread,
+   * these can then be read with `getMultipart`
+   */
+  multipart?: MultipartOptions
+  /**
+   * Compress responses with the encodings enabled in the server config, defaults to true
+   * Set this to false for responses which are already compressed
+   */
+  compress?: boolean
+}
+/** Information about a registered route, returned from `listRoutes` */
+export interface RouteInfo {
+  method: string
+  path: string
+  name?: string
+  options: RouteOptions
+}
 /**
  * This is called to start the server the address will need to include the IP and port
  * e.g. localhost:8080
@@ -65,6 +146,31 @@ export function startWithWorkerCount(address: string, workers: number): void
  * pool_per_worker_size: The size of the pool per worker
  *
  * debug: Whether to enable debug mode
+ *
+ * debug_routes_path: A path which will respond with a table of all registered routes
+ *
+ * openapi_path: A path which will respond with the OpenAPI document for the registered routes
+ *
+ * openapi_title: The title used in the OpenAPI document
+ *
+ * openapi_version: The version used in the OpenAPI document
+ *
+ * trusted_proxies: A comma separated list of CIDR ranges, the forwarding headers are only read for requests from these
+ *
+ * proxy_protocol_url: An extra url to listen on which expects each connection to start with a PROXY protocol v1 or v2 header
+ *
+ * cookie_secret: A random secret of at least 32 bytes used to sign and encrypt cookies
+ *
+ * unchecked_headers: Skip validating response header values, only use this if every header value is trusted
+ *
+ * bigint_json: How BigInt values are sent as JSON, one of error (the default), string or number
+ *
+ * compression: A comma separated list of encodings to compress responses with in order of preference, any of br, gzip and zstd
+ *
+ * compression_min_size: Responses smaller than this many bytes are sent uncompressed, defaults to 1024
+ *
+ * compression_types: A comma separated list of content types to compress, entries ending in a slash match every subtype.
+ * Defaults to text/, application/json, application/javascript, application/xml and image/svg+xml
  */
 export function startWithConfig(config: HalfBrown): void
 /**
@@ -75,6 +181,172 @@ export function stop(): boolean
 export function loadNewTemplate(groupName: string, directory: string): void
 export function reloadGroup(groupName: string): void
 export function getThreadAffinity(): Array<number>
+/** The parts of a response which never change */
+export interface PrepareResponseOptions {
+  /** Strings are sent as text/plain and buffers have their content type sniffed unless one is given */
+  body: string | Buffer
+  /** Defaults to 200 */
+  status?: number
+  headers?: Record<string, string | Array<string>>
+  contentType?: string
+}
+/**
+ * Builds a response once so it can be sent many times with `sendPrepared`
+ * without converting the body or checking the headers again
+ * Throws if the status code or any header is invalid
+ */
+export function prepareResponse(options: PrepareResponseOptions): PreparedResponse
+/** Options for sending a file from a handler with `sendFile` */
+export interface SendFileOptions {
+  /** Defaults to a type guessed from the file extension */
+  contentType?: string
+  /** Sends the file as a download with this name */
+  downloadName?: string
+  /** Allow range requests for parts of the file, defaults to true */
+  range?: boolean
+}
+/** Options for serving a directory with `serveDir` */
+export interface ServeDirOptions {
+  /** The file sent for requests to a directory, defaults to index.html, an empty string disables this */
+  index?: string
+  /** A file in the directory sent for missing paths without a file extension, for single page apps */
+  fallback?: string
+  /** Whether files starting with a dot are hidden with a 404, sent or refused with a 403, defaults to ignore */
+  dotfiles?: 'ignore' | 'allow' | 'deny'
+  /** Allow symlinks which point outside of the directory, defaults to false */
+  followSymlinks?: boolean
+  /** Send a `.br` or `.gz` file next to the requested one when the client accepts it, defaults to true */
+  precompressed?: boolean
+  /** The Cache-Control header sent with every file */
+  cacheControl?: string
+  /** Cache-Control headers by file extension e.g. `{ html: 'no-cache' }`, these replace the default */
+  cacheControlByExtension?: Record<string, string>
+}
+/**
+ * Serves the files in a directory under a url prefix, the files are sent from the worker
+ * threads without calling into JS. Routes registered for the same paths take priority
+ * Only GET and HEAD requests are served, conditional and range requests are supported
+ * Throws if the directory doesn't exist or the options are invalid
+ */
+export function serveDir(prefix: string, dir: string, options?: ServeDirOptions): void
+/** A single server-sent event */
+export interface SseMessage {
+  /** The event name, the browser fires a `message` event when this is missing */
+  event?: string
+  /** Strings are sent as they are, anything else is sent as JSON */
+  data?: any
+  /** Sent back by the browser in the Last-Event-ID header when it reconnects */
+  id?: string
+  /** How many milliseconds the browser waits before reconnecting */
+  retry?: number
+}
+/** Settings for an event stream route */
+export interface SseOptions {
+  /** Milliseconds of quiet before a keep alive comment is sent, defaults to 15000 and 0 disables it */
+  keepAlive?: number
+  /** Sent to the browser when it connects to set how many milliseconds it waits before reconnecting */
+  retry?: number
+}
+/** Settings for a broadcast channel */
+export interface SseChannelOptions {
+  /**
+   * How many of the latest events with an id are kept, a reconnecting browser is sent the events
+   * after its Last-Event-ID when it subscribes again. Defaults to 0
+   */
+  history?: number
+  /**
+   * How many bytes a subscriber can fall behind before it is disconnected, a client which stops
+   * reading would otherwise keep every event in memory. Defaults to 1MB
+   */
+  maxBuffered?: number
+}
+/**
+ * Adds a GET route which answers with a `text/event-stream`, the callback is given the open stream
+ * and the request. The stream is sent once the callback returns unless it sent another response,
+ * which can be used to turn away unauthorised clients. The request is a copy which stays readable while the stream is open
+ */
+export function sse(route: string, callback: (stream: SseStream, request: SseRequest) => void, options?: SseOptions): void
+/** Creates a channel which broadcasts events to every stream subscribed to it */
+export function createSseChannel(options?: SseChannelOptions): SseChannel
+/** Settings for a WebSocket route */
+export interface WsOptions {
+  /** The largest message in bytes a client can send, larger messages close the socket with 1009. Defaults to 1MB */
+  maxMessageSize?: number
+  /**
+   * Milliseconds between pings, a client which hasn't answered the last ping is disconnected.
+   * Defaults to 30000 and 0 disables pings
+   */
+  pingInterval?: number
+  /**
+   * How many bytes can wait to be written to a socket, a socket over it is disconnected and
+   * `onClose` is called with 1013. Defaults to 1MB
+   */
+  maxBufferedAmount?: number
+}
+/** The callbacks for a WebSocket route, each one is optional */
+export interface WsHandlers {
+  onOpen?: (socket: WsConnection, request: RequestBlob) => void
+  onMessage?: (socket: WsConnection, message: string | Buffer) => void
+  onClose?: (socket: WsConnection, code: number, reason: string) => void
+}
+/**
+ * Adds a route which upgrades GET requests to a WebSocket. `onOpen` is called with the socket and
+ * the request, sending a response from it refuses the upgrade. `onMessage` is called with strings for
+ * text frames and Buffers for binary frames, then `onClose` is called with the close code and reason
+ * Pings from the client are answered without calling into JS, sockets stop being read while the
+ * handlers are more than 1024 events behind
+ */
+export function ws(route: string, handlers: WsHandlers, options?: WsOptions): void
+/**
+ * Sends a message to every socket in a room, the frame is built once and written to each
+ * socket on the worker threads. Pass a socket id to leave out the sender
+ * Returns how many sockets the message was sent to
+ */
+export function wsBroadcast(room: string, message: string | Uint8Array, exceptId?: number): number
+/** The number of sockets in a room */
+export function wsRoomSize(room: string): number
+/** A text field from a multipart body */
+/**
+ * The attributes used when setting a cookie, signed and encrypted cookies
+ * need a cookie_secret to be set in the server config
+ */
+export interface CookieOptions {
+  path?: string
+  domain?: string
+  /** The number of seconds until the cookie expires */
+  maxAge?: number
+  /** When the cookie expires as milliseconds since the epoch e.g. `date.getTime()` */
+  expires?: number
+  secure?: boolean
+  httpOnly?: boolean
+  sameSite?: 'Strict' | 'Lax' | 'None'
+  partitioned?: boolean
+  /** Sign the value so it can't be changed by the client */
+  signed?: boolean
+  /** Encrypt the value so it can't be read or changed by the client */
+  encrypted?: boolean
+}
+export interface MultipartField {
+  name: string
+  value: string
+}
+/**
+ * A file from a multipart body, either `data` or `path` will be set depending
+ * on whether the file was kept in memory or written to a temporary file
+ */
+export interface MultipartFile {
+  name: string
+  filename?: string
+  contentType?: string
+  size: number
+  data?: Uint8Array
+  path?: string
+}
+/** The parsed parts of a multipart/form-data body */
+export interface MultipartBody {
+  fields: Array<MultipartField>
+  files: Array<MultipartFile>
+}
 export class DbConnection {
   query(query: FastStr): object
   prepareStatement(query: string, count: number): Promise<PreparedStatement>
@@ -105,6 +377,44 @@ export class RequestBlob {
    * This method will not check if a previous response has been sent doing so will result in undefined behavior but will be faster
    */
   uncheckedSendBytesText(response: Buffer): void
+  /**
+   * This needs to be called at the end of every request even if nothing is returned
+   * This sends binary data to the client, if no content type is given it is worked out from
+   * the first bytes of the data falling back to application/octet-stream
+   */
+  sendBytes(response: Uint8Array, contentType?: string): void
+  /**
+   * This needs to be called at the end of every request even if nothing is returned
+   * The same as sendBytes but arrays of 4KB or more are sent without being copied. The array must
+   * not be written to, detached or transferred until the response has been sent, so don't use
+   * this with pooled or reused buffers
+   */
+  sendBytesNoCopy(response: Uint8Array, contentType?: string): void
+  /**
+   * This needs to be called at the end of every request even if nothing is returned
+   * An alias of sendBytes for Node Buffers
+   */
+  sendBuffer(response: Buffer, contentType?: string): void
+  /**
+   * This needs to be called at the end of every request even if nothing is returned
+   * This streams a file from disk on the worker thread so it is never loaded into JS
+   * Conditional and range requests are handled, a missing file is sent as a 404
+   * Headers set on this request are sent but the status code comes from the file
+   */
+  sendFile(path: string, options?: SendFileOptions): void
+  /**
+   * This can be called in place of the other send methods to send the body a chunk at a time
+   * The status and headers are sent straight away, the body is sent with chunked transfer encoding
+   * and finishes when `end` is called on the returned stream. The content type defaults to application/octet-stream
+   * Throws if the status code or any header is invalid
+   */
+  startStream(status?: number, headers?: Record<string, string | Array<string>>): ResponseStream
+  /**
+   * This needs to be called at the end of every request even if nothing is returned
+   * This sends a response built with `prepareResponse`, the status and headers it was
+   * prepared with are used in place of any set on this request
+   */
+  sendPrepared(response: PreparedResponse): void
   /**
    * This needs to be called at the end of every request even if nothing is returned
    * This will send an empty string to the user, useful for testing
@@ -119,18 +429,19 @@ export class RequestBlob {
   /**
    * This needs to be called at the end of every request even if nothing is returned
    * This will send a JSON object to client, it will be serialized rust side so no need to stringify
+   * Values are serialized the same as JSON.stringify, toJSON is called, Buffers are sent as base64 and circular objects throw
+   * If the route has a response schema only the properties it describes are sent
    */
   sendObject(response: any): void
   /**
    * This needs to be called at the end of every request even if nothing is returned
    * This will send a JSON object to client, it will be serialized rust side so no need to stringify
-   * This needs to be a key value object, any other is undefined behaviour
+   * This is the same as sendObject
    */
   sendFastObject(response: Object): void
   /**
    * This needs to be called at the end of every request even if nothing is returned
    * This will send a JSON object to client, it will be serialized rust side so no need to stringify
-   * This needs to be a key value object, any other is undefined behaviour
    * This method will not check if a previous response has been sent doing so will result in undefined behavior but will be faster
    * The return value will only indicate if the message was sent or not
    */
@@ -156,18 +467,72 @@ export class RequestBlob {
    * This can be used to notify of a server error with a message to display
    */
   sendInternalServerErrorWithMessage(message: BuffStr): void
-  /** Add a new header to the response sent to the user */
+  /**
+   * Add a new header to the response sent to the user
+   * if the header has already been added the value is sent alongside the existing ones
+   * Throws if the name or value is not a valid header
+   */
   addHeader(key: BuffStr, value: BuffStr): void
+  /**
+   * Add a header to the response, keeping any values already added with the same name
+   * Throws if the name or value is not a valid header
+   */
+  appendHeader(key: BuffStr, value: BuffStr): void
+  /**
+   * Set a header on the response, replacing any values already added with the same name
+   * this can be used to override the default Server and Content-Type headers
+   * Throws if the name or value is not a valid header
+   */
+  setHeader(key: BuffStr, value: BuffStr): void
+  /**
+   * Set multiple headers on the response, each replacing any values already added with the same name
+   * an array of values sends the header once for each value
+   * Throws if any name or value is not a valid header, in which case none of the headers are set
+   */
+  setHeaders(headers: Record<string, string | Array<string>>): void
+  /**
+   * Remove a header from the response, including the default Server and Content-Type headers
+   * Throws if the name is not a valid header
+   */
+  removeHeader(key: BuffStr): void
+  /**
+   * Get the headers which will be sent with the response, names are lowercased and
+   * headers with multiple values have an array of values
+   */
+  getResponseHeaders(): Record<string, string | Array<string>>
+  /** Get the cookies sent with the request as an object with each name and decoded value */
+  getCookies(): HalfBrown
+  /** Get the decoded value of a cookie, this will be null if the cookie was not sent */
+  getCookie(name: string): string | null
+  /**
+   * Get the value of a cookie set with the signed option
+   * this will be null if the cookie was not sent or the signature does not match
+   */
+  getSignedCookie(name: string): string | null
+  /**
+   * Get the value of a cookie set with the encrypted option
+   * this will be null if the cookie was not sent or could not be decrypted
+   */
+  getEncryptedCookie(name: string): string | null
+  /**
+   * Add a Set-Cookie header to the response, each cookie set is sent as its own header
+   * Throws if the options are invalid or a signed or encrypted cookie is set without a cookie_secret
+   */
+  setCookie(name: string, value: string, options?: CookieOptions): void
   /**
    * Set the returning status code for this response to the user
    * Returns a boolean to indicate if the status code was set
    */
   setStatusCode(status: number): boolean
   /**
-   * Get the query parameters as an object with each key and value
-   * this will only be null if an error has occurred
+   * Get the query parameters as an object with each key and the decoded value
+   * Keys which are repeated will have an array of values
+   * Passing true for nested expands keys like `filter[status]` and `ids[]` into objects and arrays
+   * this will be null if there is no query string
    */
-  getQueryParams(): HalfBrown | null
+  getQueryParams(nested?: boolean): Record<string, any> | null
+  /** Get the raw query string without the leading `?`, this will be null if there is no query string */
+  getQueryString(): string | null
   /**
    * Get the url parameters as an object with each key and value
    * this will only be null if an error has occurred
@@ -183,11 +548,201 @@ export class RequestBlob {
    * this will only be null if an error has occurred
    */
   getHeader(name: FastStr): string | null
+  /**
+   * Get every value sent for a header, useful for headers which can be repeated
+   * this will be empty if the header was not sent
+   */
+  getHeaderValues(name: FastStr): Array<string>
   /**
    * Get the url parameters as an object with each key and value
    * this will only be null if an error has occurred
    */
   getAllHeaders(): HalfBrown
-  /** Retrieve the raw body bytes in a Uint8Array to be used */
+  /** Get the HTTP method of the request e.g. GET */
+  getMethod(): string
+  /** Get the path of the request without the query string */
+  getPath(): string
+  /**
+   * Get the full url of the request including the scheme, host and query string
+   * when the request came through a trusted proxy the forwarded scheme and host are used
+   */
+  getUrl(): string
+  /** Get the HTTP version of the request e.g. HTTP/1.1 */
+  getHttpVersion(): string
+  /** Get the scheme the request was made with e.g. http */
+  getScheme(): string
+  /**
+   * Get the address of the socket which made the request as ip:port
+   * this will be null if the address is not known
+   */
+  getPeerAddress(): string | null
+  /**
+   * Get the ip address of the socket which made the request
+   * this will be null if the address is not known
+   */
+  getPeerIp(): string | null
+  /**
+   * Get the address the client connected to as ip:port, for PROXY protocol listeners
+   * this is the destination sent by the proxy. This will be null if the address is not known
+   */
+  getDestinationAddress(): string | null
+  /**
+   * Get the ip address of the client, when the request came through a trusted proxy
+   * this is read from the Forwarded or X-Forwarded-For headers otherwise it is the peer ip
+   * this will be null if the address is not known
+   */
+  getClientIp(): string | null
+  /**
+   * Get the scheme the client used, when the request came through a trusted proxy
+   * this is read from the Forwarded or X-Forwarded-Proto headers
+   */
+  getClientScheme(): string
+  /**
+   * Get the host the client requested, when the request came through a trusted proxy
+   * this is read from the Forwarded or X-Forwarded-Host headers otherwise the Host header is used
+   * this will be null if no host was sent
+   */
+  getClientHost(): string | null
+  /** Retrieve a copy of the body bytes in a Uint8Array, gzip, deflate and br bodies are already decoded */
   getBody(): Uint8Array
+  /**
+   * Retrieve the body bytes without copying, the Uint8Array shares memory with the server
+   * The body is handed over to JS so getBody, getJsonBody and getFormData won't see it afterwards
+   */
+  getBodyNoCopy(): Uint8Array
+  /**
+   * Retrieve the body parsed as JSON, this will be null if there is no body
+   * Routes registered with the jsonBody option have this parsed on the worker thread before the handler is called
+   * Throws if the content type is not application/json and an InvalidArg error if the body is malformed
+   */
+  getJsonBody(): any
+  /**
+   * Retrieve an application/x-www-form-urlencoded body as an object, keys which are repeated
+   * will have an array of values. This will be null if there is no body
+   */
+  getFormData(): Record<string, string | string[]> | null
+  /**
+   * Retrieve the fields and files from a multipart/form-data body, the route needs to be
+   * registered with the multipart option. This will be null if the body was not multipart
+   * The parts can only be retrieved once
+   */
+  getMultipart(): MultipartBody | null
+}
+/** A handle to a response built with `prepareResponse`, send it with `sendPrepared` */
+export class PreparedResponse { }
+/** Writes a response to the client a chunk at a time, returned from `startStream` */
+export class ResponseStream {
+  /**
+   * Queues a chunk to be sent, chunks are always sent in the order they are written
+   * The promise resolves once there is room for more data and rejects if the client has disconnected
+   */
+  write(chunk: string | Uint8Array): Promise<void>
+  /**
+   * Sends an optional last chunk and finishes the response
+   * Calling this again without a chunk does nothing
+   */
+  end(chunk?: string | Uint8Array): void
+  /** True once the client has disconnected or the response has been fully sent */
+  isClosed(): boolean
+}
+/** An open event stream to a single client, passed to the handlers registered with `sse` */
+export class SseStream {
+  /**
+   * Sends an event to the client, the promise resolves once there is room for more events
+   * and rejects if the client has disconnected
+   * Throws if the id or event name contain a line break
+   */
+  send(message: SseMessage): Promise<void>
+  /** Ends the response, the browser will reconnect unless it is told not to */
+  close(): void
+  /** True once the client has disconnected or the stream has been closed */
+  isClosed(): boolean
+  /** The Last-Event-ID header sent by a reconnecting browser */
+  lastEventId(): string | null
+}
+/**
+ * The request which opened an event stream, passed to the handlers registered with `sse`.
+ * The request is copied so it can be read for as long as the stream is open, a response
+ * can only be sent in place of the stream before the handler returns
+ */
+export class SseRequest {
+  /** Get the value of a header, this will be null if the header was not sent */
+  getHeader(name: string): string | null
+  /** Get every header as an object with each name and value */
+  getAllHeaders(): Record<string, string>
+  /** Get the HTTP method of the request e.g. GET */
+  getMethod(): string
+  /** Get the path of the request without the query string */
+  getPath(): string
+  /**
+   * Get the query parameters the same as `RequestBlob.getQueryParams`
+   * this will be null if there is no query string
+   */
+  getQueryParams(nested?: boolean): Record<string, any> | null
+  /**
+   * Get the url parameters as an object with each key and value
+   * this will only be null if an error has occurred
+   */
+  getUrlParams(): Record<string, string> | null
+  /**
+   * Set the status code of a response sent instead of the stream
+   * Throws once the handler has returned
+   */
+  setStatusCode(status: number): boolean
+  /**
+   * Set a header on a response sent instead of the stream
+   * Throws once the handler has returned
+   */
+  setHeader(key: string, value: string): void
+  /** Sends text instead of the stream, throws once the handler has returned */
+  sendText(response: string): void
+  /** Sends an object as JSON instead of the stream, throws once the handler has returned */
+  sendObject(response: any): void
+}
+/**
+ * Sends each event to every subscribed stream, the event is encoded once
+ * and written to the connections without calling into JS for each one
+ */
+export class SseChannel {
+  /**
+   * Adds a stream to the channel, it is removed once the client disconnects or the stream is closed
+   * If the browser sent a Last-Event-ID still in the history the events after it are sent first
+   */
+  subscribe(stream: SseStream): void
+  /**
+   * Sends an event to every subscribed stream, returns how many streams it was sent to
+   * Streams which have fallen more than `maxBuffered` bytes behind are disconnected and removed
+   * Throws if the id or event name contain a line break
+   */
+  send(message: SseMessage): number
+  /** The number of streams subscribed, closed streams are only removed when the next event is sent */
+  subscriberCount(): number
+}
+/** An open WebSocket, passed to the handlers registered with `ws` */
+export class WsConnection {
+  /**
+   * Sends a text frame for strings and a binary frame for arrays
+   * Returns false if the socket has already closed
+   */
+  send(message: string | Uint8Array): boolean
+  /**
+   * Closes the connection with a code and reason, the code defaults to 1000
+   * Throws if the code can't be sent in a close frame or the reason is longer than 123 bytes
+   */
+  close(code?: number, reason?: string): void
+  /** True once either side has closed the connection */
+  isClosed(): boolean
+  /** The number of bytes waiting to be written to the connection */
+  bufferedAmount(): number
+  /** A number unique to this connection */
+  id(): number
+  /**
+   * Adds the connection to a room so it receives messages sent with `wsBroadcast`
+   * Connections leave every room when they close
+   */
+  join(room: string): void
+  /** Removes the connection from a room */
+  leave(room: string): void
+  /** The rooms this connection has joined */
+  rooms(): Array<string>
 }
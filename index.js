@@ -236,7 +236,7 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { DbConnection, connectDb, PreparedStatement, Methods, newRoute, get, post, put, patch, RequestBlob, start, startWithWorkerCount, startWithConfig, stop, loadNewTemplate, reloadGroup, getThreadAffinity } = nativeBinding
+const { DbConnection, connectDb, PreparedStatement, Methods, newRoute, get, post, put, patch, staticRoute, listRoutes, openApiDocument, RequestBlob, start, startWithWorkerCount, startWithConfig, stop, loadNewTemplate, reloadGroup, getThreadAffinity, prepareResponse, PreparedResponse, ResponseStream, serveDir, SseStream, SseRequest, sse, SseChannel, createSseChannel, WsConnection, ws, wsBroadcast, wsRoomSize } = nativeBinding
 
 module.exports.DbConnection = DbConnection
 module.exports.connectDb = connectDb
@@ -247,6 +247,9 @@ module.exports.get = get
 module.exports.post = post
 module.exports.put = put
 module.exports.patch = patch
+module.exports.staticRoute = staticRoute
+module.exports.listRoutes = listRoutes
+module.exports.openApiDocument = openApiDocument
 module.exports.RequestBlob = RequestBlob
 module.exports.start = start
 module.exports.startWithWorkerCount = startWithWorkerCount
@@ -255,3 +258,16 @@ module.exports.stop = stop
 module.exports.loadNewTemplate = loadNewTemplate
 module.exports.reloadGroup = reloadGroup
 module.exports.getThreadAffinity = getThreadAffinity
+module.exports.prepareResponse = prepareResponse
+module.exports.PreparedResponse = PreparedResponse
+module.exports.ResponseStream = ResponseStream
+module.exports.serveDir = serveDir
+module.exports.SseStream = SseStream
+module.exports.SseRequest = SseRequest
+module.exports.sse = sse
+module.exports.SseChannel = SseChannel
+module.exports.createSseChannel = createSseChannel
+module.exports.WsConnection = WsConnection
+module.exports.ws = ws
+module.exports.wsBroadcast = wsBroadcast
+module.exports.wsRoomSize = wsRoomSize
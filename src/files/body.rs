@@ -0,0 +1,75 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_http::body::{BodySize, MessageBody};
+use bytes::{Bytes, BytesMut};
+use tokio::{fs::File, io::{AsyncRead, ReadBuf}};
+
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Streams part of a file in chunks, the file is never held in memory all at once
+pub struct FileBody {
+    file: Option<File>,
+    remaining: u64,
+    buffer: Option<BytesMut>,
+}
+
+impl FileBody {
+    /// The file must already be positioned at the start of the part to send
+    #[inline]
+    pub fn new(file: File, length: u64) -> Self {
+        Self { file: Some(file), remaining: length, buffer: None }
+    }
+
+    /// Used for HEAD requests, the length is sent without reading the file
+    #[inline]
+    pub fn headers_only(length: u64) -> Self {
+        Self { file: None, remaining: length, buffer: None }
+    }
+}
+
+impl MessageBody for FileBody {
+    type Error = io::Error;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        BodySize::Sized(self.remaining)
+    }
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = &mut *self;
+
+        let file = match &mut this.file {
+            Some(res) if this.remaining > 0 => res,
+            _ => return Poll::Ready(None),
+        };
+
+        let chunk = this.remaining.min(CHUNK_SIZE) as usize;
+        let buffer = this.buffer.get_or_insert_with(|| BytesMut::zeroed(chunk));
+
+        let mut read_buf = ReadBuf::new(&mut buffer[..]);
+        match Pin::new(file).poll_read(cx, &mut read_buf) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e))),
+            Poll::Ready(Ok(())) => {}
+        }
+
+        let read = read_buf.filled().len();
+        if read == 0 {
+            return Poll::Ready(Some(Err(io::ErrorKind::UnexpectedEof.into())));
+        }
+
+        let mut buffer = match this.buffer.take() {
+            Some(res) => res,
+            None => return Poll::Ready(None),
+        };
+
+        buffer.truncate(read);
+        this.remaining -= read as u64;
+
+        Poll::Ready(Some(Ok(buffer.freeze())))
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::Path;
+
+/// Content types by file extension, text types include the charset
+static MIME_TYPES: &[(&str, &str)] = &[
+    ("html", "text/html; charset=UTF-8"),
+    ("htm", "text/html; charset=UTF-8"),
+    ("css", "text/css; charset=UTF-8"),
+    ("js", "text/javascript; charset=UTF-8"),
+    ("mjs", "text/javascript; charset=UTF-8"),
+    ("json", "application/json; charset=UTF-8"),
+    ("map", "application/json; charset=UTF-8"),
+    ("webmanifest", "application/manifest+json; charset=UTF-8"),
+    ("txt", "text/plain; charset=UTF-8"),
+    ("csv", "text/csv; charset=UTF-8"),
+    ("md", "text/markdown; charset=UTF-8"),
+    ("xml", "application/xml; charset=UTF-8"),
+    ("svg", "image/svg+xml; charset=UTF-8"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("ico", "image/x-icon"),
+    ("bmp", "image/bmp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("wasm", "application/wasm"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("flac", "audio/flac"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+];
+
+/// Guesses the content type from the file extension, unknown files are sent as application/octet-stream
+#[inline]
+pub fn guess_mime(path: &Path) -> &'static str {
+    let extension = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(res) => res,
+        None => return "application/octet-stream",
+    };
+
+    MIME_TYPES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+        .map_or("application/octet-stream", |(_, content_type)| *content_type)
+}
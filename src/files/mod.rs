@@ -0,0 +1,6 @@
+pub mod body;
+pub mod mime;
+pub mod mounts;
+pub mod node_functions;
+pub mod send;
+pub mod serve;
@@ -0,0 +1,216 @@
+use std::{
+    cell::UnsafeCell,
+    cmp::Reverse,
+    io,
+    path::{Path, PathBuf},
+};
+
+use actix_http::{body::BoxBody, header::HeaderValue, Method, Request, Response, StatusCode};
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use percent_encoding::percent_decode_str;
+
+use super::serve::{serve_file, status_response, ServeOptions};
+
+/// What happens to requests for files or directories starting with a dot
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DotFiles {
+    Ignore,
+    Allow,
+    Deny,
+}
+
+/// A directory served under a url prefix
+pub struct DirMount {
+    /// The url prefix without a trailing slash, the root mount is an empty string
+    pub prefix: String,
+    /// The canonical path of the directory
+    pub root: PathBuf,
+    pub index: Option<String>,
+    pub fallback: Option<String>,
+    pub dotfiles: DotFiles,
+    pub follow_symlinks: bool,
+    pub precompressed: bool,
+    pub cache_control: Option<HeaderValue>,
+    /// Cache-Control values by lower case file extension, these take priority over the default
+    pub cache_rules: Vec<(String, HeaderValue)>,
+}
+
+enum Resolved {
+    File(PathBuf),
+    NotFound,
+    Forbidden,
+}
+
+lazy_static! {
+    static ref REGISTERED_MOUNTS: RwLock<Vec<DirMount>> = RwLock::new(Vec::new());
+}
+
+struct MountCell(UnsafeCell<Vec<DirMount>>);
+
+unsafe impl Sync for MountCell {}
+
+static MOUNTS: MountCell = MountCell(UnsafeCell::new(Vec::new()));
+
+#[cold]
+pub fn add_mount(mount: DirMount) {
+    REGISTERED_MOUNTS.write().push(mount);
+}
+
+/// Moves the registered mounts to where the workers read them, longest prefixes are checked first.
+/// This must only be called before the server starts
+#[cold]
+pub fn initialise_mounts() {
+    let mut mounts = std::mem::take(&mut *REGISTERED_MOUNTS.write());
+    mounts.sort_by_key(|mount| Reverse(mount.prefix.len()));
+
+    let mounts_ref = unsafe { &mut *MOUNTS.0.get() };
+    *mounts_ref = mounts;
+}
+
+#[inline(always)]
+fn get_mounts() -> &'static [DirMount] {
+    unsafe { &*MOUNTS.0.get() }
+}
+
+/// Finds the mount serving this path, only GET and HEAD requests are served from a directory
+#[inline(always)]
+pub fn find_mount(path: &str, method: &Method) -> Option<&'static DirMount> {
+    let mounts = get_mounts();
+    if mounts.is_empty() || (method != Method::GET && method != Method::HEAD) {
+        return None;
+    }
+
+    mounts.iter().find(|mount| {
+        path.strip_prefix(mount.prefix.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+#[inline]
+fn is_file_path(path: &Path) -> io::Result<bool> {
+    Ok(std::fs::metadata(path)?.is_file())
+}
+
+impl DirMount {
+    /// Turns the rest of the url into a path inside the directory. Each segment is decoded on its
+    /// own so an encoded slash can't be used to build a path, and `..` is never allowed
+    #[inline]
+    fn relative_path(&self, rest: &str) -> Result<PathBuf, Resolved> {
+        let mut relative = PathBuf::new();
+
+        for segment in rest.split('/').filter(|segment| !segment.is_empty() && *segment != ".") {
+            let decoded = percent_decode_str(segment)
+                .decode_utf8()
+                .map_err(|_| Resolved::NotFound)?;
+
+            if decoded == ".." || decoded.contains(['/', '\\', '\0']) {
+                return Err(Resolved::Forbidden);
+            }
+
+            if decoded.starts_with('.') {
+                match self.dotfiles {
+                    DotFiles::Allow => {}
+                    DotFiles::Ignore => return Err(Resolved::NotFound),
+                    DotFiles::Deny => return Err(Resolved::Forbidden),
+                }
+            }
+
+            relative.push(decoded.as_ref());
+        }
+
+        Ok(relative)
+    }
+
+    /// Symlinks are only followed when they stay inside the directory unless the mount allows it
+    #[inline]
+    fn check_inside_root(&self, path: &Path) -> io::Result<bool> {
+        if self.follow_symlinks {
+            return Ok(true);
+        }
+
+        Ok(path.canonicalize()?.starts_with(&self.root))
+    }
+
+    /// Works out which file answers the request, directories use the index file
+    /// and missing paths without an extension use the fallback file
+    fn resolve(&self, rest: &str) -> io::Result<Resolved> {
+        let relative = match self.relative_path(rest) {
+            Ok(res) => res,
+            Err(resolved) => return Ok(resolved),
+        };
+
+        let mut path = self.root.join(&relative);
+
+        match std::fs::metadata(&path) {
+            Ok(meta) if meta.is_dir() => {
+                let index = match &self.index {
+                    Some(res) => res,
+                    None => return Ok(Resolved::NotFound),
+                };
+
+                path.push(index);
+                if !is_file_path(&path).unwrap_or(false) {
+                    return Ok(Resolved::NotFound);
+                }
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let fallback = match &self.fallback {
+                    Some(res) if relative.extension().is_none() => res,
+                    _ => return Ok(Resolved::NotFound),
+                };
+
+                path = self.root.join(fallback);
+            }
+            Err(e) => return Err(e),
+        }
+
+        if !self.check_inside_root(&path)? {
+            return Ok(Resolved::Forbidden);
+        }
+
+        Ok(Resolved::File(path))
+    }
+
+    #[inline]
+    fn cache_control_for(&self, path: &Path) -> Option<HeaderValue> {
+        let extension = path.extension().and_then(|ext| ext.to_str());
+
+        let rule = extension.and_then(|extension| {
+            self.cache_rules
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+        });
+
+        match rule {
+            Some((_, value)) => Some(value.clone()),
+            None => self.cache_control.clone(),
+        }
+    }
+
+    /// Answers a request for a file in this directory, the path lookups
+    /// touch the file system so they are run on the blocking pool
+    pub async fn serve(&'static self, req: &Request) -> Response<BoxBody> {
+        let rest = req.path()[self.prefix.len()..].to_string();
+        let resolved = tokio::task::spawn_blocking(move || self.resolve(&rest)).await;
+
+        let path = match resolved {
+            Ok(Ok(Resolved::File(path))) => path,
+            Ok(Ok(Resolved::Forbidden)) => return status_response(StatusCode::FORBIDDEN),
+            Ok(Ok(Resolved::NotFound)) => return status_response(StatusCode::NOT_FOUND),
+            Ok(Err(e)) if e.kind() == io::ErrorKind::NotFound => return status_response(StatusCode::NOT_FOUND),
+            _ => return status_response(StatusCode::INTERNAL_SERVER_ERROR),
+        };
+
+        let options = ServeOptions {
+            cache_control: self.cache_control_for(&path),
+            precompressed: self.precompressed,
+            root: (!self.follow_symlinks).then_some(self.root.as_path()),
+            ranges: true,
+            ..Default::default()
+        };
+
+        serve_file(req, &path, &options).await
+    }
+}
@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use actix_http::header::HeaderValue;
+use napi::Result;
+
+use crate::{
+    napi::halfbrown::HalfBrown,
+    request::helpers::{make_js_error_string, to_header_value},
+};
+
+use super::mounts::{add_mount, DirMount, DotFiles};
+
+/// Options for serving a directory with `serveDir`
+#[napi(object)]
+#[derive(Default)]
+pub struct ServeDirOptions {
+    /// The file sent for requests to a directory, defaults to index.html, an empty string disables this
+    pub index: Option<String>,
+    /// A file in the directory sent for missing paths without a file extension, for single page apps
+    pub fallback: Option<String>,
+    /// Whether files starting with a dot are hidden with a 404, sent or refused with a 403, defaults to ignore
+    #[napi(ts_type = "'ignore' | 'allow' | 'deny'")]
+    pub dotfiles: Option<String>,
+    /// Allow symlinks which point outside of the directory, defaults to false
+    pub follow_symlinks: Option<bool>,
+    /// Send a `.br` or `.gz` file next to the requested one when the client accepts it, defaults to true
+    pub precompressed: Option<bool>,
+    /// The Cache-Control header sent with every file
+    pub cache_control: Option<String>,
+    /// Cache-Control headers by file extension e.g. `{ html: 'no-cache' }`, these replace the default
+    #[napi(ts_type = "Record<string, string>")]
+    pub cache_control_by_extension: Option<HalfBrown<String, String>>,
+}
+
+#[cold]
+fn parse_dotfiles(value: Option<&str>) -> Result<DotFiles> {
+    match value {
+        None | Some("ignore") => Ok(DotFiles::Ignore),
+        Some("allow") => Ok(DotFiles::Allow),
+        Some("deny") => Ok(DotFiles::Deny),
+        Some(other) => Err(make_js_error_string(format!("Invalid dotfiles value: {}", other))),
+    }
+}
+
+#[cold]
+fn cache_value(value: String) -> Result<HeaderValue> {
+    to_header_value(&actix_http::header::CACHE_CONTROL, value.into())
+}
+
+#[cold]
+#[napi(ts_args_type = "prefix: string, dir: string, options?: ServeDirOptions")]
+/// Serves the files in a directory under a url prefix, the files are sent from the worker
+/// threads without calling into JS. Routes registered for the same paths take priority
+/// Only GET and HEAD requests are served, conditional and range requests are supported
+/// Throws if the directory doesn't exist or the options are invalid
+pub fn serve_dir(prefix: String, dir: String, options: Option<ServeDirOptions>) -> Result<()> {
+    let options = options.unwrap_or_default();
+
+    let root = Path::new(&dir)
+        .canonicalize()
+        .ok()
+        .filter(|root| root.is_dir())
+        .ok_or_else(|| make_js_error_string(format!("Directory not found: {}", dir)))?;
+
+    let prefix = format!("/{}", prefix.trim_matches('/'));
+    let prefix = if prefix == "/" { String::new() } else { prefix };
+
+    let cache_rules = options
+        .cache_control_by_extension
+        .map(|rules| rules.0)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(extension, value)| Ok((extension.trim_start_matches('.').to_ascii_lowercase(), cache_value(value)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    add_mount(DirMount {
+        prefix,
+        root,
+        index: Some(options.index.unwrap_or_else(|| "index.html".to_string())).filter(|index| !index.is_empty()),
+        fallback: options.fallback,
+        dotfiles: parse_dotfiles(options.dotfiles.as_deref())?,
+        follow_symlinks: options.follow_symlinks.unwrap_or(false),
+        precompressed: options.precompressed.unwrap_or(true),
+        cache_control: options.cache_control.map(cache_value).transpose()?,
+        cache_rules,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use actix_http::{
+    body::BoxBody,
+    header::{HeaderValue, CONTENT_TYPE},
+    Request, Response,
+};
+use bytes::Bytes;
+use napi::Result;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+
+use crate::request::helpers::to_header_value;
+
+use super::serve::{serve_file, ServeOptions};
+
+/// Options for sending a file from a handler with `sendFile`
+#[napi(object)]
+#[derive(Default)]
+pub struct SendFileOptions {
+    /// Defaults to a type guessed from the file extension
+    pub content_type: Option<String>,
+    /// Sends the file as a download with this name
+    pub download_name: Option<String>,
+    /// Allow range requests for parts of the file, defaults to true
+    pub range: Option<bool>,
+}
+
+/// A file to stream once the handler has finished, it is opened on the worker thread
+pub struct FileResponse {
+    path: PathBuf,
+    options: ServeOptions,
+}
+
+/// Builds an attachment header with a plain fallback name and the full UTF-8 name
+#[inline]
+fn attachment_header(name: &str) -> HeaderValue {
+    let fallback: String = name
+        .chars()
+        .map(|c| if (c.is_ascii_graphic() && c != '"' && c != '\\') || c == ' ' { c } else { '_' })
+        .collect();
+
+    let value = format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback,
+        utf8_percent_encode(name, NON_ALPHANUMERIC)
+    );
+
+    HeaderValue::from_str(&value).unwrap_or_else(|_| HeaderValue::from_static("attachment"))
+}
+
+impl FileResponse {
+    /// Checks the options on the JS thread so any mistakes are thrown to the handler
+    #[inline]
+    pub fn new(path: String, options: Option<SendFileOptions>) -> Result<Self> {
+        let options = options.unwrap_or_default();
+
+        let content_type = match options.content_type {
+            Some(res) => Some(to_header_value(&CONTENT_TYPE, Bytes::from(res))?),
+            None => None,
+        };
+
+        let disposition = options.download_name.as_deref().map(attachment_header);
+
+        Ok(Self {
+            path: PathBuf::from(path),
+            options: ServeOptions {
+                content_type,
+                disposition,
+                ranges: options.range.unwrap_or(true),
+                ..Default::default()
+            },
+        })
+    }
+
+    #[inline]
+    pub async fn send(&self, req: &Request) -> Response<BoxBody> {
+        serve_file(req, &self.path, &self.options).await
+    }
+}
@@ -0,0 +1,358 @@
+use std::{
+    ffi::OsString,
+    fs::Metadata,
+    io::{self, SeekFrom},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use actix_http::{
+    body::BoxBody,
+    header::{
+        HeaderMap, HeaderValue, HttpDate, ACCEPT_ENCODING, ACCEPT_RANGES, CACHE_CONTROL, CONTENT_DISPOSITION,
+        CONTENT_ENCODING, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE,
+        LAST_MODIFIED, RANGE, SERVER, VARY,
+    },
+    HttpMessage, Method, Request, Response, StatusCode,
+};
+use tokio::{fs::File, io::AsyncSeekExt};
+
+use crate::response::WALKER_SERVER;
+
+use super::{body::FileBody, mime::guess_mime};
+
+/// How a file is sent, shared by directory mounts and `sendFile`
+#[derive(Default)]
+pub struct ServeOptions {
+    pub content_type: Option<HeaderValue>,
+    pub cache_control: Option<HeaderValue>,
+    pub disposition: Option<HeaderValue>,
+    /// Look for `.br` and `.gz` files next to the requested one
+    pub precompressed: bool,
+    /// Precompressed files are only sent when they resolve inside this directory, set by mounts
+    /// which don't follow symlinks so a linked `.gz` can't be used to leave the root
+    pub root: Option<&'static Path>,
+    pub ranges: bool,
+}
+
+/// The precompressed files we look for, in order of preference
+static ENCODINGS: &[(&str, &str)] = &[("br", "br"), ("gzip", "gz")];
+
+/// The file picked to answer the request, possibly a precompressed sibling
+struct OpenedFile {
+    file: File,
+    meta: Metadata,
+    encoding: Option<&'static str>,
+}
+
+#[inline]
+fn header_str<'a>(req: &'a Request, name: &actix_http::header::HeaderName) -> Option<&'a str> {
+    req.headers().get(name).and_then(|val| val.to_str().ok())
+}
+
+/// Checks if the encoding is listed in Accept-Encoding without a zero quality
+#[inline]
+fn accepts_encoding(req: &Request, encoding: &str) -> bool {
+    req.headers()
+        .get_all(ACCEPT_ENCODING)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .any(|entry| {
+            let mut parts = entry.split(';');
+            let name = parts.next().unwrap_or("").trim();
+            let rejected = parts.any(|param| matches!(param.trim(), "q=0" | "q=0.0" | "q=0.00" | "q=0.000"));
+
+            name.eq_ignore_ascii_case(encoding) && !rejected
+        })
+}
+
+#[inline]
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".");
+    name.push(suffix);
+
+    PathBuf::from(name)
+}
+
+#[inline]
+async fn open_file(path: &Path) -> io::Result<(File, Metadata)> {
+    let file = File::open(path).await?;
+    let meta = file.metadata().await?;
+
+    if !meta.is_file() {
+        return Err(io::ErrorKind::NotFound.into());
+    }
+
+    Ok((file, meta))
+}
+
+/// Missing files count as outside so the sibling is skipped
+#[inline]
+async fn is_inside_root(path: &Path, root: Option<&Path>) -> bool {
+    let root = match root {
+        Some(res) => res,
+        None => return true,
+    };
+
+    match tokio::fs::canonicalize(path).await {
+        Ok(canonical) => canonical.starts_with(root),
+        Err(_) => false,
+    }
+}
+
+/// Opens the file, preferring a precompressed sibling the client can accept.
+/// Ranges are only supported on the original file so these are skipped for range requests
+async fn open_best(req: &Request, path: &Path, options: &ServeOptions) -> io::Result<OpenedFile> {
+    let wants_range = options.ranges && req.headers().contains_key(RANGE);
+
+    if options.precompressed && !wants_range {
+        for (encoding, suffix) in ENCODINGS {
+            if !accepts_encoding(req, encoding) {
+                continue;
+            }
+
+            let sibling = with_suffix(path, suffix);
+            if !is_inside_root(&sibling, options.root).await {
+                continue;
+            }
+
+            if let Ok((file, meta)) = open_file(&sibling).await {
+                return Ok(OpenedFile { file, meta, encoding: Some(encoding) });
+            }
+        }
+    }
+
+    let (file, meta) = open_file(path).await?;
+    Ok(OpenedFile { file, meta, encoding: None })
+}
+
+/// A strong validator built from the size and modification time of the file
+#[inline]
+fn build_etag(meta: &Metadata, encoding: Option<&str>) -> String {
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |time| time.as_nanos());
+
+    match encoding {
+        Some(encoding) => format!("\"{:x}-{:x}-{}\"", meta.len(), modified, encoding),
+        None => format!("\"{:x}-{:x}\"", meta.len(), modified),
+    }
+}
+
+#[inline]
+fn etag_matches(list: &str, etag: &str) -> bool {
+    if list.trim() == "*" {
+        return true;
+    }
+
+    list.split(',')
+        .map(|entry| entry.trim())
+        .any(|entry| entry.strip_prefix("W/").unwrap_or(entry) == etag)
+}
+
+#[inline]
+fn is_not_modified(req: &Request, etag: &str, last_modified: Option<HttpDate>) -> bool {
+    if let Some(list) = header_str(req, &IF_NONE_MATCH) {
+        return etag_matches(list, etag);
+    }
+
+    let since = header_str(req, &IF_MODIFIED_SINCE).and_then(|val| val.parse::<HttpDate>().ok());
+    match (since, last_modified) {
+        (Some(since), Some(modified)) => modified <= since,
+        _ => false,
+    }
+}
+
+enum ByteRange {
+    Full,
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Parses a single `bytes=` range, anything else including multiple ranges sends the whole file
+#[inline]
+fn parse_range(value: &str, len: u64) -> ByteRange {
+    let spec = match value.trim().strip_prefix("bytes=") {
+        Some(res) if !res.contains(',') => res.trim(),
+        _ => return ByteRange::Full,
+    };
+
+    let (start, end) = match spec.split_once('-') {
+        Some(res) => res,
+        None => return ByteRange::Full,
+    };
+
+    if start.is_empty() {
+        return match end.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if len == 0 => ByteRange::Unsatisfiable,
+            Ok(suffix) => ByteRange::Partial(len.saturating_sub(suffix), len - 1),
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let start = match start.parse::<u64>() {
+        Ok(res) => res,
+        Err(_) => return ByteRange::Full,
+    };
+
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+
+    if end.is_empty() {
+        return ByteRange::Partial(start, len - 1);
+    }
+
+    match end.parse::<u64>() {
+        Ok(end) if end >= start => ByteRange::Partial(start, end.min(len - 1)),
+        _ => ByteRange::Full,
+    }
+}
+
+/// If-Range only allows the partial response when the validator still matches
+#[inline]
+fn if_range_matches(req: &Request, etag: &str, last_modified: Option<HttpDate>) -> bool {
+    let value = match header_str(req, &IF_RANGE) {
+        Some(res) => res.trim(),
+        None => return true,
+    };
+
+    if value.starts_with('"') {
+        return value == etag;
+    }
+
+    match (value.parse::<HttpDate>().ok(), last_modified) {
+        (Some(date), Some(modified)) => date == modified,
+        _ => false,
+    }
+}
+
+/// An empty response with the default Server header
+#[inline]
+pub fn status_response(status: StatusCode) -> Response<BoxBody> {
+    let mut rsp = Response::new(status);
+    rsp.headers_mut().insert(SERVER, WALKER_SERVER.clone());
+
+    rsp
+}
+
+#[inline]
+fn error_response(e: &io::Error) -> Response<BoxBody> {
+    let status = match e.kind() {
+        io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    status_response(status)
+}
+
+#[inline]
+fn insert_validators(headers: &mut HeaderMap, etag: &str, last_modified: Option<HttpDate>, options: &ServeOptions) {
+    if let Ok(etag) = HeaderValue::from_str(etag) {
+        headers.insert(ETAG, etag);
+    }
+
+    if let Some(modified) = last_modified.and_then(|date| HeaderValue::from_str(&date.to_string()).ok()) {
+        headers.insert(LAST_MODIFIED, modified);
+    }
+
+    if let Some(cache_control) = &options.cache_control {
+        headers.insert(CACHE_CONTROL, cache_control.clone());
+    }
+}
+
+async fn try_serve_file(req: &Request, path: &Path, options: &ServeOptions) -> io::Result<Response<BoxBody>> {
+    let OpenedFile { mut file, meta, encoding } = open_best(req, path, options).await?;
+
+    let len = meta.len();
+    let etag = build_etag(&meta, encoding);
+    let last_modified = meta.modified().ok().map(HttpDate::from);
+
+    if is_not_modified(req, &etag, last_modified) {
+        let mut rsp = status_response(StatusCode::NOT_MODIFIED);
+        insert_validators(rsp.headers_mut(), &etag, last_modified, options);
+
+        return Ok(rsp);
+    }
+
+    let range = match header_str(req, &RANGE) {
+        Some(value) if options.ranges && encoding.is_none() && if_range_matches(req, &etag, last_modified) => {
+            parse_range(value, len)
+        }
+        _ => ByteRange::Full,
+    };
+
+    let (status, start, length) = match range {
+        ByteRange::Full => (StatusCode::OK, 0, len),
+        ByteRange::Partial(start, end) => (StatusCode::PARTIAL_CONTENT, start, end - start + 1),
+        ByteRange::Unsatisfiable => {
+            let mut rsp = status_response(StatusCode::RANGE_NOT_SATISFIABLE);
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", len)) {
+                rsp.headers_mut().insert(CONTENT_RANGE, value);
+            }
+
+            return Ok(rsp);
+        }
+    };
+
+    let body = if req.method() == Method::HEAD {
+        FileBody::headers_only(length)
+    } else {
+        if start > 0 {
+            file.seek(SeekFrom::Start(start)).await?;
+        }
+
+        FileBody::new(file, length)
+    };
+
+    let mut rsp = Response::with_body(status, body).map_into_boxed_body();
+    let headers = rsp.headers_mut();
+
+    headers.insert(SERVER, WALKER_SERVER.clone());
+    insert_validators(headers, &etag, last_modified, options);
+
+    let content_type = match &options.content_type {
+        Some(res) => res.clone(),
+        None => HeaderValue::from_static(guess_mime(path)),
+    };
+    headers.insert(CONTENT_TYPE, content_type);
+
+    if options.ranges {
+        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    }
+
+    if options.precompressed {
+        headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+    }
+
+    if let Some(encoding) = encoding {
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+    }
+
+    if status == StatusCode::PARTIAL_CONTENT {
+        let value = format!("bytes {}-{}/{}", start, start + length - 1, len);
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(CONTENT_RANGE, value);
+        }
+    }
+
+    if let Some(disposition) = &options.disposition {
+        headers.insert(CONTENT_DISPOSITION, disposition.clone());
+    }
+
+    Ok(rsp)
+}
+
+/// Streams a file to the client handling conditional and range requests,
+/// missing files are answered with a 404 and unreadable ones with a 403
+pub async fn serve_file(req: &Request, path: &Path, options: &ServeOptions) -> Response<BoxBody> {
+    match try_serve_file(req, path, options).await {
+        Ok(res) => res,
+        Err(e) => error_response(&e),
+    }
+}
@@ -18,10 +18,17 @@ mod templates;
 mod object_pool;
 mod tokio_workers;
 mod extras;
+mod files;
+mod sse;
+mod ws;
 
 pub use db::node_functions::*;
 pub use request::node_functions::*;
 pub use router::node_functions::*;
 pub use server::node_functions::*;
 pub use templates::{load_new_template, reload_group};
-pub use extras::node_functions::*;
\ No newline at end of file
+pub use response::prepared::prepare_response;
+pub use extras::node_functions::*;
+pub use files::node_functions::*;
+pub use sse::node_functions::*;
+pub use ws::node_functions::*;
\ No newline at end of file
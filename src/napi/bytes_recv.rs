@@ -1,45 +1,132 @@
-use std::ptr;
+use std::{ffi::c_void, ptr, sync::OnceLock};
 
 use bytes::Bytes;
 use napi::{
     bindgen_prelude::FromNapiValue,
     check_status,
-    sys::{self, napi_env, napi_value},
-    Result, TypedArrayType, Error, Status,
+    sys::{self, napi_env, napi_ref, napi_value},
+    Error, Result, Status, TypedArrayType,
 };
 
-pub struct JsBytes(pub bytes::Bytes);
+use super::tsfn::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+
+/// Buffers smaller than this are copied, holding a reference costs more than the copy
+const ZERO_COPY_MIN_LEN: usize = 4 * 1024;
+
+/// References can only be deleted on the JS thread, so dropped buffers are sent back through this
+static RELEASE_TSFN: OnceLock<ThreadsafeFunction> = OnceLock::new();
+
+unsafe extern "C" fn release_reference(env: napi_env, _js_callback: napi_value, _context: *mut c_void, data: *mut c_void) {
+    // env can be null when shutting down
+    if !env.is_null() {
+        sys::napi_delete_reference(env, data as napi_ref);
+    }
+}
+
+#[cold]
+unsafe fn init_release_tsfn(env: napi_env) -> Result<()> {
+    if RELEASE_TSFN.get().is_some() {
+        return Ok(());
+    }
+
+    let tsfn = ThreadsafeFunction::create_with_callback(env, ptr::null_mut(), 0, release_reference)?;
+
+    // Releasing buffers shouldn't keep the process alive
+    tsfn.unref(env)?;
+    let _ = RELEASE_TSFN.set(tsfn);
+
+    Ok(())
+}
+
+/// Keeps the JS buffer alive while the bytes are in use on the worker threads
+struct BufferOwner {
+    reference: napi_ref,
+    data: *const u8,
+    len: usize,
+}
+
+unsafe impl Send for BufferOwner {}
+unsafe impl Sync for BufferOwner {}
+
+impl AsRef<[u8]> for BufferOwner {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u8] {
+        unsafe { &*ptr::slice_from_raw_parts(self.data, self.len) }
+    }
+}
+
+impl Drop for BufferOwner {
+    fn drop(&mut self) {
+        if let Some(tsfn) = RELEASE_TSFN.get() {
+            tsfn.call_raw(self.reference as *mut c_void, ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    }
+}
+
+#[inline(always)]
+unsafe fn get_uint8_array(env: napi_env, napi_val: napi_value) -> Result<(*const u8, usize)> {
+    let mut typed_array_type = 0;
+    let mut length = 0;
+    let mut data = ptr::null_mut();
+    let mut array_buffer = ptr::null_mut();
+    let mut byte_offset = 0;
+
+    check_status!(
+        sys::napi_get_typedarray_info(
+            env,
+            napi_val,
+            &mut typed_array_type,
+            &mut length,
+            &mut data,
+            &mut array_buffer,
+            &mut byte_offset,
+        ),
+        "Get TypedArray info failed"
+    )?;
+
+    if typed_array_type != TypedArrayType::Uint8 as i32 {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!("Expected utf8, got {}", typed_array_type),
+        ));
+    }
+
+    Ok((data as *const u8, length))
+}
+
+/// A JS Uint8Array which is copied into Rust, JS is free to reuse the array straight away
+pub struct JsBytes(pub Bytes);
 
 impl FromNapiValue for JsBytes {
     #[inline(always)]
     unsafe fn from_napi_value(env: napi_env, napi_val: napi_value) -> Result<Self> {
-        let mut typed_array_type = 0;
-        let mut length = 0;
-        let mut data = ptr::null_mut();
-        let mut array_buffer = ptr::null_mut();
-        let mut byte_offset = 0;
+        let (data, len) = get_uint8_array(env, napi_val)?;
+        Ok(Self(Bytes::copy_from_slice(&*ptr::slice_from_raw_parts(data, len))))
+    }
+}
 
-        check_status!(
-            sys::napi_get_typedarray_info(
-                env,
-                napi_val,
-                &mut typed_array_type,
-                &mut length,
-                &mut data,
-                &mut array_buffer,
-                &mut byte_offset,
-            ),
-            "Get TypedArray info failed"
-        )?;
+/// A JS Uint8Array which is handed to Rust without copying, the array is kept alive until the
+/// bytes are dropped. Nothing stops JS writing to, detaching or transferring the ArrayBuffer so
+/// this must only be used where callers promise not to touch the array afterwards
+pub struct SharedBytes(pub Bytes);
 
-        if typed_array_type != TypedArrayType::Uint8 as i32 {
-            return Err(Error::new(
-              Status::InvalidArg,
-              format!("Expected utf8, got {}", typed_array_type),
-            ));
+impl FromNapiValue for SharedBytes {
+    #[inline(always)]
+    unsafe fn from_napi_value(env: napi_env, napi_val: napi_value) -> Result<Self> {
+        let (data, len) = get_uint8_array(env, napi_val)?;
+        if len < ZERO_COPY_MIN_LEN {
+            return Ok(Self(Bytes::copy_from_slice(&*ptr::slice_from_raw_parts(data, len))));
         }
 
-        let buffer = Bytes::copy_from_slice(&*ptr::slice_from_raw_parts(data as *mut u8, length));
-        Ok(Self(buffer))
+        init_release_tsfn(env)?;
+
+        let mut reference = ptr::null_mut();
+        check_status!(
+            sys::napi_create_reference(env, napi_val, 1, &mut reference),
+            "Failed to create reference to buffer"
+        )?;
+
+        let owner = BufferOwner { reference, data, len };
+        Ok(Self(Bytes::from_owner(owner)))
     }
 }
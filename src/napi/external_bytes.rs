@@ -0,0 +1,96 @@
+use std::{ffi::c_void, ptr};
+
+use bytes::Bytes;
+use napi::{
+    bindgen_prelude::{ToNapiValue, TypeName},
+    check_status,
+    sys::{self, napi_env, napi_value},
+    Result, ValueType,
+};
+
+/// Bytes which are given to JS as a Uint8Array, by default this is backed by an external
+/// ArrayBuffer which borrows the bytes and releases them once JS garbage collects it
+pub struct ExternalBytes {
+    data: Bytes,
+    copy: bool,
+}
+
+impl ExternalBytes {
+    #[inline(always)]
+    pub fn external(data: Bytes) -> Self {
+        Self { data, copy: false }
+    }
+
+    /// V8 won't allow the same memory to back two external ArrayBuffers,
+    /// so anything handed out more than once needs to be copied
+    #[inline(always)]
+    pub fn copied(data: Bytes) -> Self {
+        Self { data, copy: true }
+    }
+}
+
+unsafe extern "C" fn finalize_bytes(_env: napi_env, _data: *mut c_void, hint: *mut c_void) {
+    drop(Box::from_raw(hint as *mut Bytes));
+}
+
+#[inline(always)]
+unsafe fn create_external(env: napi_env, data: Bytes, result: &mut napi_value) -> sys::napi_status {
+    let len = data.len();
+    let data_ptr = data.as_ptr() as *mut c_void;
+    let hint = Box::into_raw(Box::new(data));
+
+    let status = sys::napi_create_external_arraybuffer(env, data_ptr, len, Some(finalize_bytes), hint as *mut c_void, result);
+
+    if status != sys::Status::napi_ok {
+        drop(Box::from_raw(hint));
+    }
+
+    status
+}
+
+#[cold]
+unsafe fn create_copied(env: napi_env, data: &[u8], result: &mut napi_value) -> Result<()> {
+    let mut buffer_data = ptr::null_mut();
+
+    check_status!(
+        sys::napi_create_arraybuffer(env, data.len(), &mut buffer_data, result),
+        "Failed to create ArrayBuffer"
+    )?;
+
+    if !data.is_empty() {
+        ptr::copy_nonoverlapping(data.as_ptr(), buffer_data as *mut u8, data.len());
+    }
+
+    Ok(())
+}
+
+impl ToNapiValue for ExternalBytes {
+    unsafe fn to_napi_value(env: napi_env, val: Self) -> Result<napi_value> {
+        let len = val.data.len();
+        let mut array_buffer = ptr::null_mut();
+
+        // Runtimes such as Electron don't allow external buffers, in which case we copy
+        let use_external = !val.copy && len > 0;
+        if !use_external || create_external(env, val.data.clone(), &mut array_buffer) != sys::Status::napi_ok {
+            create_copied(env, &val.data, &mut array_buffer)?;
+        }
+
+        let mut result = ptr::null_mut();
+        check_status!(
+            sys::napi_create_typedarray(env, sys::TypedarrayType::uint8_array, len, array_buffer, 0, &mut result),
+            "Failed to create Uint8Array"
+        )?;
+
+        Ok(result)
+    }
+}
+
+impl TypeName for ExternalBytes {
+    fn type_name() -> &'static str {
+        "Uint8Array"
+    }
+
+    fn value_type() -> ValueType {
+        ValueType::Object
+    }
+}
@@ -0,0 +1,97 @@
+use std::ptr;
+
+use napi::{
+    bindgen_prelude::{ToNapiValue, TypeName},
+    check_status,
+    sys::{self, napi_env, napi_value},
+    Result, ValueType,
+};
+use serde_json::{Number, Value};
+
+/// A JSON value which is built straight into JS values on conversion
+pub struct JsonValue(pub Value);
+
+impl ToNapiValue for JsonValue {
+    unsafe fn to_napi_value(raw_env: napi_env, val: Self) -> Result<napi_value> {
+        json_to_js_value(raw_env, &val.0)
+    }
+}
+
+impl TypeName for JsonValue {
+    fn type_name() -> &'static str {
+        "any"
+    }
+
+    fn value_type() -> ValueType {
+        ValueType::Unknown
+    }
+}
+
+#[inline]
+pub unsafe fn json_to_js_value(env: napi_env, value: &Value) -> Result<napi_value> {
+    let mut result = ptr::null_mut();
+
+    match value {
+        Value::Null => check_status!(sys::napi_get_null(env, &mut result))?,
+        Value::Bool(b) => check_status!(sys::napi_get_boolean(env, *b, &mut result))?,
+        Value::Number(n) => return number_to_js_value(env, n),
+        Value::String(s) => return str_to_js_value(env, s),
+        Value::Array(items) => {
+            check_status!(sys::napi_create_array_with_length(env, items.len(), &mut result))?;
+
+            for (i, item) in items.iter().enumerate() {
+                let js_item = json_to_js_value(env, item)?;
+
+                check_status!(
+                    sys::napi_set_element(env, result, i as u32, js_item),
+                    "Failed to set element with index `{}`",
+                    i,
+                )?;
+            }
+        }
+        Value::Object(map) => {
+            check_status!(sys::napi_create_object(env, &mut result))?;
+
+            for (key, item) in map {
+                let js_key = str_to_js_value(env, key)?;
+                let js_item = json_to_js_value(env, item)?;
+
+                check_status!(
+                    sys::napi_set_property(env, result, js_key, js_item),
+                    "Failed to set property with field `{}`",
+                    key,
+                )?;
+            }
+        }
+    };
+
+    Ok(result)
+}
+
+#[inline]
+unsafe fn number_to_js_value(env: napi_env, number: &Number) -> Result<napi_value> {
+    let mut result = ptr::null_mut();
+
+    match number.as_i64() {
+        Some(int) => check_status!(sys::napi_create_int64(env, int, &mut result))?,
+        None => check_status!(sys::napi_create_double(
+            env,
+            number.as_f64().unwrap_or(f64::NAN),
+            &mut result
+        ))?,
+    };
+
+    Ok(result)
+}
+
+#[inline]
+unsafe fn str_to_js_value(env: napi_env, string: &str) -> Result<napi_value> {
+    let mut result = ptr::null_mut();
+
+    check_status!(
+        sys::napi_create_string_utf8(env, string.as_ptr() as *const _, string.len(), &mut result),
+        "Failed to create napi `string`",
+    )?;
+
+    Ok(result)
+}
@@ -0,0 +1,486 @@
+use std::{
+    ptr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::{BufMut, Bytes, BytesMut};
+use napi::{
+    check_status,
+    sys::{self, napi_env, napi_value},
+    Error, Result, Status, TypedArrayType,
+};
+
+const MAX_DEPTH: usize = 1024;
+
+const TO_JSON: &[u8] = b"toJSON\0";
+
+/// How BigInt values are written, the default matches JSON.stringify and throws
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum BigIntMode {
+    Error = 0,
+    String = 1,
+    Number = 2,
+}
+
+impl BigIntMode {
+    #[cold]
+    pub fn from_config(value: &str) -> Option<Self> {
+        match value {
+            "error" => Some(Self::Error),
+            "string" => Some(Self::String),
+            "number" => Some(Self::Number),
+            _ => None,
+        }
+    }
+}
+
+static BIGINT_MODE: AtomicU8 = AtomicU8::new(BigIntMode::Error as u8);
+
+/// Sets how BigInt values are written, this must only be called before the server starts
+#[cold]
+pub fn write_bigint_mode(mode: BigIntMode) {
+    BIGINT_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+#[inline(always)]
+fn get_bigint_mode() -> BigIntMode {
+    match BIGINT_MODE.load(Ordering::Relaxed) {
+        1 => BigIntMode::String,
+        2 => BigIntMode::Number,
+        _ => BigIntMode::Error,
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn make_json_error(reason: &'static str) -> Error {
+    Error::new(Status::InvalidArg, reason.to_string())
+}
+
+/// The key a value was found under, this is only turned into a JS value if toJSON is called
+#[derive(Clone, Copy)]
+pub(crate) enum JsonKey {
+    Root,
+    Name(napi_value),
+    Index(u32),
+}
+
+/// Walks a JS value writing it straight out as JSON, following the rules of JSON.stringify
+pub(crate) struct JsonWriter {
+    pub(crate) env: napi_env,
+    pub(crate) out: BytesMut,
+    stack: Vec<napi_value>,
+    scratch: Vec<u8>,
+}
+
+impl JsonWriter {
+    #[inline(always)]
+    pub(crate) fn new(env: napi_env) -> Self {
+        Self {
+            env,
+            out: BytesMut::with_capacity(128),
+            stack: Vec::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn finish(self) -> Bytes {
+        self.out.freeze()
+    }
+
+    /// Writes a value which may need toJSON applying, anything which can't be
+    /// represented in JSON is written as null
+    #[inline]
+    pub(crate) unsafe fn write_any(&mut self, value: napi_value, key: JsonKey) -> Result<()> {
+        let (value, value_type) = self.resolve(value, key)?;
+        if Self::is_skipped(value_type) {
+            self.out.put_slice(b"null");
+            return Ok(());
+        }
+
+        self.write_value(value, value_type)
+    }
+
+    #[inline(always)]
+    pub(crate) unsafe fn type_of(&self, value: napi_value) -> Result<i32> {
+        let mut value_type = 0;
+        check_status!(sys::napi_typeof(self.env, value, &mut value_type), "Failed to get type of value")?;
+
+        Ok(value_type)
+    }
+
+    /// Values which are left out of objects and written as null in arrays
+    #[inline(always)]
+    pub(crate) fn is_skipped(value_type: i32) -> bool {
+        value_type == sys::ValueType::napi_undefined
+            || value_type == sys::ValueType::napi_function
+            || value_type == sys::ValueType::napi_symbol
+            || value_type == sys::ValueType::napi_external
+    }
+
+    #[inline]
+    unsafe fn key_to_js(&self, key: JsonKey) -> Result<napi_value> {
+        let index_str;
+        let text = match key {
+            JsonKey::Name(name) => return Ok(name),
+            JsonKey::Root => "",
+            JsonKey::Index(index) => {
+                index_str = index.to_string();
+                &index_str
+            }
+        };
+
+        let mut result = ptr::null_mut();
+        check_status!(
+            sys::napi_create_string_utf8(self.env, text.as_ptr() as *const _, text.len(), &mut result),
+            "Failed to create napi `string`",
+        )?;
+
+        Ok(result)
+    }
+
+    /// Calls toJSON if the object has one, returning the value which should be written
+    #[inline]
+    unsafe fn apply_to_json(&self, value: napi_value, key: JsonKey) -> Result<napi_value> {
+        let mut to_json = ptr::null_mut();
+        check_status!(
+            sys::napi_get_named_property(self.env, value, TO_JSON.as_ptr() as *const _, &mut to_json),
+            "Failed to get toJSON"
+        )?;
+
+        if self.type_of(to_json)? != sys::ValueType::napi_function {
+            return Ok(value);
+        }
+
+        let args = [self.key_to_js(key)?];
+        let mut result = ptr::null_mut();
+        check_status!(
+            sys::napi_call_function(self.env, value, to_json, 1, args.as_ptr(), &mut result),
+            "Failed to call toJSON"
+        )?;
+
+        Ok(result)
+    }
+
+    /// Resolves toJSON and returns the value to write and its type
+    #[inline]
+    pub(crate) unsafe fn resolve(&self, value: napi_value, key: JsonKey) -> Result<(napi_value, i32)> {
+        let value_type = self.type_of(value)?;
+        if value_type != sys::ValueType::napi_object || self.is_uint8_array(value)? {
+            return Ok((value, value_type));
+        }
+
+        let resolved = self.apply_to_json(value, key)?;
+        if resolved == value {
+            return Ok((value, value_type));
+        }
+
+        Ok((resolved, self.type_of(resolved)?))
+    }
+
+    #[inline(always)]
+    pub(crate) unsafe fn is_uint8_array(&self, value: napi_value) -> Result<bool> {
+        let mut is_typed_array = false;
+        check_status!(sys::napi_is_typedarray(self.env, value, &mut is_typed_array), "Failed to check TypedArray")?;
+
+        if !is_typed_array {
+            return Ok(false);
+        }
+
+        let mut typed_array_type = 0;
+        check_status!(
+            sys::napi_get_typedarray_info(
+                self.env,
+                value,
+                &mut typed_array_type,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            ),
+            "Get TypedArray info failed"
+        )?;
+
+        Ok(typed_array_type == TypedArrayType::Uint8 as i32)
+    }
+
+    pub(crate) unsafe fn write_value(&mut self, value: napi_value, value_type: i32) -> Result<()> {
+        match value_type {
+            sys::ValueType::napi_null => self.out.put_slice(b"null"),
+            sys::ValueType::napi_boolean => {
+                let mut result = false;
+                check_status!(sys::napi_get_value_bool(self.env, value, &mut result), "Failed to get boolean")?;
+                self.out.put_slice(if result { b"true" } else { b"false" });
+            }
+            sys::ValueType::napi_number => {
+                let mut result = 0.0;
+                check_status!(sys::napi_get_value_double(self.env, value, &mut result), "Failed to get number")?;
+                self.write_number(result);
+            }
+            sys::ValueType::napi_string => {
+                self.read_string(value)?;
+                write_escaped(&mut self.out, &self.scratch);
+            }
+            sys::ValueType::napi_bigint => self.write_bigint(value)?,
+            sys::ValueType::napi_object => self.write_object(value)?,
+            _ => self.out.put_slice(b"null"),
+        };
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn write_number(&mut self, number: f64) {
+        if !number.is_finite() {
+            self.out.put_slice(b"null");
+            return;
+        }
+
+        // Integers are written without a fraction the same as JS does, anything
+        // integral within the i64 range converts exactly
+        if number.fract() == 0.0 && number.abs() < 9.0e18 {
+            let mut buffer = itoa::Buffer::new();
+            self.out.put_slice(buffer.format(number as i64).as_bytes());
+        } else {
+            let mut buffer = ryu::Buffer::new();
+            self.out.put_slice(buffer.format_finite(number).as_bytes());
+        }
+    }
+
+    /// Reads a JS string into the scratch buffer
+    #[inline(always)]
+    unsafe fn read_string(&mut self, value: napi_value) -> Result<()> {
+        let mut len = 0;
+        check_status!(
+            sys::napi_get_value_string_utf8(self.env, value, ptr::null_mut(), 0, &mut len),
+            "Failed to convert napi `string` into rust type `String`",
+        )?;
+
+        self.scratch.clear();
+        self.scratch.reserve(len + 1);
+
+        let mut written = 0;
+        check_status!(
+            sys::napi_get_value_string_utf8(self.env, value, self.scratch.as_mut_ptr() as *mut _, len + 1, &mut written),
+            "Failed to convert napi `string` into rust type `String`",
+        )?;
+
+        self.scratch.set_len(written);
+        Ok(())
+    }
+
+    #[inline]
+    unsafe fn write_bigint(&mut self, value: napi_value) -> Result<()> {
+        let mode = get_bigint_mode();
+        if mode == BigIntMode::Error {
+            return Err(make_json_error("Do not know how to serialize a BigInt"));
+        }
+
+        let mut as_string = ptr::null_mut();
+        check_status!(sys::napi_coerce_to_string(self.env, value, &mut as_string), "Failed to convert BigInt")?;
+        self.read_string(as_string)?;
+
+        if mode == BigIntMode::String {
+            write_escaped(&mut self.out, &self.scratch);
+        } else {
+            self.out.put_slice(&self.scratch);
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    unsafe fn write_uint8_array(&mut self, value: napi_value) -> Result<()> {
+        let mut length = 0;
+        let mut data = ptr::null_mut();
+
+        check_status!(
+            sys::napi_get_typedarray_info(
+                self.env,
+                value,
+                ptr::null_mut(),
+                &mut length,
+                &mut data,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            ),
+            "Get TypedArray info failed"
+        )?;
+
+        let bytes = if length == 0 { &[][..] } else { &*ptr::slice_from_raw_parts(data as *const u8, length) };
+
+        self.out.put_u8(b'"');
+        self.out.put_slice(STANDARD.encode(bytes).as_bytes());
+        self.out.put_u8(b'"');
+
+        Ok(())
+    }
+
+    /// Errors if the object is already being written further up, this would never finish
+    #[inline]
+    unsafe fn enter(&mut self, value: napi_value) -> Result<()> {
+        if self.stack.len() >= MAX_DEPTH {
+            return Err(make_json_error("Object is too deeply nested to convert to JSON"));
+        }
+
+        for parent in &self.stack {
+            let mut equal = false;
+            check_status!(sys::napi_strict_equals(self.env, *parent, value, &mut equal), "Failed to compare values")?;
+
+            if equal {
+                return Err(make_json_error("Converting circular structure to JSON"));
+            }
+        }
+
+        self.stack.push(value);
+        Ok(())
+    }
+
+    unsafe fn write_object(&mut self, value: napi_value) -> Result<()> {
+        if self.is_uint8_array(value)? {
+            return self.write_uint8_array(value);
+        }
+
+        self.enter(value)?;
+
+        let mut is_array = false;
+        check_status!(sys::napi_is_array(self.env, value, &mut is_array), "Failed to check array")?;
+
+        if is_array {
+            self.write_array_items(value)?;
+        } else {
+            self.write_object_entries(value)?;
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    unsafe fn write_array_items(&mut self, value: napi_value) -> Result<()> {
+        let mut length = 0;
+        check_status!(sys::napi_get_array_length(self.env, value, &mut length), "Failed to get array length")?;
+
+        self.out.put_u8(b'[');
+
+        for index in 0..length {
+            if index > 0 {
+                self.out.put_u8(b',');
+            }
+
+            let mut item = ptr::null_mut();
+            check_status!(sys::napi_get_element(self.env, value, index, &mut item), "Failed to get element")?;
+
+            let (item, item_type) = self.resolve(item, JsonKey::Index(index))?;
+            if Self::is_skipped(item_type) {
+                self.out.put_slice(b"null");
+            } else {
+                self.write_value(item, item_type)?;
+            }
+        }
+
+        self.out.put_u8(b']');
+        Ok(())
+    }
+
+    unsafe fn write_object_entries(&mut self, value: napi_value) -> Result<()> {
+        // JSON.stringify only writes own enumerable string keys, inherited properties are skipped
+        let mut names = ptr::null_mut();
+        check_status!(
+            sys::napi_get_all_property_names(
+                self.env,
+                value,
+                sys::KeyCollectionMode::own_only,
+                sys::KeyFilter::enumerable | sys::KeyFilter::skip_symbols,
+                sys::KeyConversion::numbers_to_strings,
+                &mut names,
+            ),
+            "Failed to get property names of given object"
+        )?;
+
+        let mut length = 0;
+        check_status!(sys::napi_get_array_length(self.env, names, &mut length), "Failed to get array length")?;
+
+        self.out.put_u8(b'{');
+        let mut first = true;
+
+        for index in 0..length {
+            let mut key = ptr::null_mut();
+            check_status!(sys::napi_get_element(self.env, names, index, &mut key), "Failed to get element")?;
+
+            let mut item = ptr::null_mut();
+            check_status!(sys::napi_get_property(self.env, value, key, &mut item), "Failed to get property")?;
+
+            let (item, item_type) = self.resolve(item, JsonKey::Name(key))?;
+            if Self::is_skipped(item_type) {
+                continue;
+            }
+
+            if !first {
+                self.out.put_u8(b',');
+            }
+            first = false;
+
+            self.read_string(key)?;
+            write_escaped(&mut self.out, &self.scratch);
+            self.out.put_u8(b':');
+
+            self.write_value(item, item_type)?;
+        }
+
+        self.out.put_u8(b'}');
+        Ok(())
+    }
+}
+
+#[inline(always)]
+fn escape_for(byte: u8) -> Option<&'static [u8]> {
+    match byte {
+        b'"' => Some(b"\\\""),
+        b'\\' => Some(b"\\\\"),
+        b'\n' => Some(b"\\n"),
+        b'\r' => Some(b"\\r"),
+        b'\t' => Some(b"\\t"),
+        0x08 => Some(b"\\b"),
+        0x0C => Some(b"\\f"),
+        _ => None,
+    }
+}
+
+/// Writes a quoted JSON string, copying runs of bytes which don't need escaping in one go
+#[inline]
+pub(crate) fn write_escaped(out: &mut BytesMut, input: &[u8]) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    out.reserve(input.len() + 2);
+    out.put_u8(b'"');
+
+    let mut start = 0;
+    for (i, &byte) in input.iter().enumerate() {
+        if byte >= 0x20 && byte != b'"' && byte != b'\\' {
+            continue;
+        }
+
+        out.put_slice(&input[start..i]);
+        start = i + 1;
+
+        match escape_for(byte) {
+            Some(escaped) => out.put_slice(escaped),
+            None => out.put_slice(&[b'\\', b'u', b'0', b'0', HEX[(byte >> 4) as usize], HEX[(byte & 0xF) as usize]]),
+        }
+    }
+
+    out.put_slice(&input[start..]);
+    out.put_u8(b'"');
+}
+
+/// Serialises any JS value to JSON bytes, following the rules of JSON.stringify
+#[inline]
+pub unsafe fn write_json(env: napi_env, value: napi_value) -> Result<Bytes> {
+    let mut writer = JsonWriter::new(env);
+    writer.write_any(value, JsonKey::Root)?;
+
+    Ok(writer.finish())
+}
@@ -1,8 +1,10 @@
 pub mod tsfn;
 pub mod bytes_recv;
+pub mod external_bytes;
 pub mod fast_str;
 pub mod buff_str;
 pub mod halfbrown;
 pub mod postgres;
 pub mod postgres_rows;
-pub mod fast_serde;
\ No newline at end of file
+pub mod json_writer;
+pub mod json_value;
\ No newline at end of file
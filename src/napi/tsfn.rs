@@ -100,6 +100,17 @@ impl ThreadsafeFunction {
         env: sys::napi_env,
         func: sys::napi_value,
         max_queue_size: usize,
+    ) -> Result<Self> {
+        Self::create_with_callback(env, func, max_queue_size, call_js_cb)
+    }
+
+    /// Creates the function with a custom `call_js` callback, this receives the data pointer
+    /// passed to `call_raw` and is responsible for calling the JS function
+    pub(crate) fn create_with_callback(
+        env: sys::napi_env,
+        func: sys::napi_value,
+        max_queue_size: usize,
+        call_js: unsafe extern "C" fn(sys::napi_env, sys::napi_value, *mut c_void, *mut c_void),
     ) -> Result<Self> {
         let mut async_resource_name = ptr::null_mut();
         let s = "napi_rs_threadsafe_function";
@@ -123,7 +134,7 @@ impl ThreadsafeFunction {
                 ptr,
                 Some(thread_finalize_cb),
                 ptr,
-                Some(call_js_cb),
+                Some(call_js),
                 &mut raw_tsfn,
             )
         })?;
@@ -143,6 +154,18 @@ impl ThreadsafeFunction {
         unsafe { sys::napi_call_threadsafe_function(self.raw_tsfn, value as *mut _, mode.into()) }
             .into()
     }
+
+    /// Stops the function from keeping the event loop alive, this must be called on the JS thread
+    pub fn unref(&self, env: sys::napi_env) -> Result<()> {
+        check_status!(unsafe { sys::napi_unref_threadsafe_function(env, self.raw_tsfn) })
+    }
+
+    /// Passes any pointer to the `call_js` callback the function was created with
+    #[inline(always)]
+    pub fn call_raw(&self, data: *mut c_void, mode: ThreadsafeFunctionCallMode) -> Status {
+        unsafe { sys::napi_call_threadsafe_function(self.raw_tsfn, data, mode.into()) }
+            .into()
+    }
 }
 
 impl Drop for ThreadsafeFunction {
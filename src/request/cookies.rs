@@ -0,0 +1,169 @@
+use std::cell::UnsafeCell;
+
+use actix_http::header::{HeaderMap, COOKIE};
+use bytes::Bytes;
+use cookie::{Cookie, CookieJar, Key, SameSite};
+use halfbrown::HashMap;
+use napi::Result;
+use time::OffsetDateTime;
+
+use crate::napi::halfbrown::HalfBrown;
+
+use super::helpers::{make_js_error, make_js_error_string};
+
+/// The attributes used when setting a cookie, signed and encrypted cookies
+/// need a cookie_secret to be set in the server config
+#[napi(object)]
+#[derive(Default)]
+pub struct CookieOptions {
+    pub path: Option<String>,
+    pub domain: Option<String>,
+    /// The number of seconds until the cookie expires
+    pub max_age: Option<i64>,
+    /// When the cookie expires as milliseconds since the epoch e.g. `date.getTime()`
+    pub expires: Option<f64>,
+    pub secure: Option<bool>,
+    pub http_only: Option<bool>,
+    #[napi(ts_type = "'Strict' | 'Lax' | 'None'")]
+    pub same_site: Option<String>,
+    pub partitioned: Option<bool>,
+    /// Sign the value so it can't be changed by the client
+    pub signed: Option<bool>,
+    /// Encrypt the value so it can't be read or changed by the client
+    pub encrypted: Option<bool>,
+}
+
+struct KeyCell(UnsafeCell<Option<Key>>);
+
+unsafe impl Sync for KeyCell {}
+
+static COOKIE_KEY: KeyCell = KeyCell(UnsafeCell::new(None));
+
+/// Derives the signing and encryption keys, this must only be called before the server starts
+#[cold]
+pub fn write_cookie_key(secret: Option<&str>) {
+    let key_ref = unsafe { &mut *COOKIE_KEY.0.get() };
+    *key_ref = secret.map(|secret| Key::derive_from(secret.as_bytes()));
+}
+
+#[inline(always)]
+fn get_cookie_key() -> Result<&'static Key> {
+    let key = unsafe { &*COOKIE_KEY.0.get() };
+    key.as_ref().ok_or_else(|| make_js_error("No cookie_secret was set in the server config."))
+}
+
+#[inline]
+fn request_cookies(headers: &HeaderMap) -> impl Iterator<Item = Cookie<'static>> + '_ {
+    headers
+        .get_all(COOKIE)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| Cookie::split_parse_encoded(val.to_owned()))
+        .filter_map(|cookie| cookie.ok())
+}
+
+/// Collects the cookies sent with the request, if a name is repeated the first value is kept
+#[inline]
+pub fn parse_cookies(headers: &HeaderMap) -> HalfBrown<String, String> {
+    let mut map = HashMap::new();
+
+    for cookie in request_cookies(headers) {
+        if !map.contains_key(cookie.name()) {
+            map.insert(cookie.name().to_string(), cookie.value().to_string());
+        }
+    }
+
+    HalfBrown(map)
+}
+
+#[inline]
+pub fn find_cookie(headers: &HeaderMap, name: &str) -> Option<Cookie<'static>> {
+    request_cookies(headers).find(|cookie| cookie.name() == name)
+}
+
+/// Checks the signature of a cookie returning the original value, None if it was tampered with
+#[inline]
+pub fn verify_signed(cookie: Cookie<'static>) -> Result<Option<String>> {
+    let jar = CookieJar::new();
+    let verified = jar.signed(get_cookie_key()?).verify(cookie);
+
+    Ok(verified.map(|cookie| cookie.value().to_string()))
+}
+
+/// Decrypts a cookie returning the original value, None if it was tampered with
+#[inline]
+pub fn decrypt(cookie: Cookie<'static>) -> Result<Option<String>> {
+    let jar = CookieJar::new();
+    let decrypted = jar.private(get_cookie_key()?).decrypt(cookie);
+
+    Ok(decrypted.map(|cookie| cookie.value().to_string()))
+}
+
+#[cold]
+fn parse_same_site(value: &str) -> Result<SameSite> {
+    match value.to_ascii_lowercase().as_str() {
+        "strict" => Ok(SameSite::Strict),
+        "lax" => Ok(SameSite::Lax),
+        "none" => Ok(SameSite::None),
+        _ => Err(make_js_error_string(format!("Invalid sameSite value: {}", value))),
+    }
+}
+
+#[inline]
+fn apply_options(cookie: &mut Cookie<'static>, options: &CookieOptions) -> Result<()> {
+    if let Some(path) = &options.path {
+        cookie.set_path(path.clone());
+    }
+
+    if let Some(domain) = &options.domain {
+        cookie.set_domain(domain.clone());
+    }
+
+    if let Some(max_age) = options.max_age {
+        cookie.set_max_age(time::Duration::seconds(max_age));
+    }
+
+    if let Some(expires) = options.expires {
+        let nanos = (expires * 1_000_000.0) as i128;
+        let date = OffsetDateTime::from_unix_timestamp_nanos(nanos)
+            .map_err(|_| make_js_error("Invalid expires date for cookie."))?;
+
+        cookie.set_expires(date);
+    }
+
+    if let Some(same_site) = &options.same_site {
+        cookie.set_same_site(parse_same_site(same_site)?);
+    }
+
+    cookie.set_secure(options.secure);
+    cookie.set_http_only(options.http_only);
+    cookie.set_partitioned(options.partitioned);
+
+    Ok(())
+}
+
+/// Builds the value of a Set-Cookie header, signing or encrypting the value if asked to
+#[inline]
+pub fn build_set_cookie(name: String, value: String, options: Option<CookieOptions>) -> Result<Bytes> {
+    let options = options.unwrap_or_default();
+    let mut cookie = Cookie::new(name, value);
+    apply_options(&mut cookie, &options)?;
+
+    if options.signed.unwrap_or(false) || options.encrypted.unwrap_or(false) {
+        let key = get_cookie_key()?;
+        let name = cookie.name().to_string();
+        let mut jar = CookieJar::new();
+
+        if options.encrypted.unwrap_or(false) {
+            jar.private_mut(key).add(cookie);
+        } else {
+            jar.signed_mut(key).add(cookie);
+        }
+
+        cookie = match jar.get(&name) {
+            Some(res) => res.clone(),
+            None => return Err(make_js_error("Unable to secure cookie.")),
+        };
+    }
+
+    Ok(Bytes::from(cookie.encoded().to_string()))
+}
@@ -0,0 +1,222 @@
+use std::path::PathBuf;
+
+use actix_http::header::{HeaderMap, CONTENT_TYPE};
+use bytes::Bytes;
+use napi::bindgen_prelude::Uint8Array;
+use serde_json::{Map, Value};
+
+/// A text field from a multipart body
+#[napi(object)]
+pub struct MultipartField {
+    pub name: String,
+    pub value: String,
+}
+
+/// A file from a multipart body, either `data` or `path` will be set depending
+/// on whether the file was kept in memory or written to a temporary file
+#[napi(object)]
+pub struct MultipartFile {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub size: f64,
+    pub data: Option<Uint8Array>,
+    pub path: Option<String>,
+}
+
+/// The parsed parts of a multipart/form-data body
+#[napi(object)]
+pub struct MultipartBody {
+    pub fields: Vec<MultipartField>,
+    pub files: Vec<MultipartFile>,
+}
+
+pub enum PartData {
+    Memory(Bytes),
+    File(PathBuf, u64),
+}
+
+/// A single part read from a multipart body on the worker thread
+pub struct MultipartPart {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: PartData,
+}
+
+#[inline]
+pub fn parts_to_body(parts: Vec<MultipartPart>) -> MultipartBody {
+    let mut fields = vec![];
+    let mut files = vec![];
+
+    for part in parts {
+        if part.filename.is_none() {
+            if let PartData::Memory(data) = part.data {
+                let value = String::from_utf8_lossy(&data).into_owned();
+                fields.push(MultipartField { name: part.name, value });
+                continue;
+            }
+        }
+
+        let (size, data, path) = match part.data {
+            PartData::Memory(data) => (data.len() as f64, Some(data.into()), None),
+            PartData::File(path, size) => (size as f64, None, Some(path.to_string_lossy().into_owned())),
+        };
+
+        files.push(MultipartFile {
+            name: part.name,
+            filename: part.filename,
+            content_type: part.content_type,
+            size,
+            data,
+            path,
+        });
+    }
+
+    MultipartBody { fields, files }
+}
+
+#[inline(always)]
+pub fn is_urlencoded_content_type(headers: &HeaderMap) -> bool {
+    let content_type = match headers.get(CONTENT_TYPE).and_then(|val| val.to_str().ok()) {
+        Some(res) => res,
+        None => return false,
+    };
+
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.eq_ignore_ascii_case("application/x-www-form-urlencoded")
+}
+
+/// Adds a value to the map, if the key is already there the values are collected into an array
+#[inline]
+pub fn insert_multi_value(map: &mut Map<String, Value>, key: String, value: Value) {
+    match map.get_mut(&key) {
+        Some(Value::Array(existing)) => existing.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            map.insert(key, value);
+        }
+    }
+}
+
+/// Parses an `application/x-www-form-urlencoded` body, decoding percent escapes and `+`
+#[inline]
+pub fn parse_urlencoded(input: &[u8]) -> Map<String, Value> {
+    parse_query_string(input, false)
+}
+
+/// Parses a query string, decoding percent escapes and `+`. Keys without a value are kept
+/// with an empty string and repeated keys collect their values into an array.
+/// With `nested` enabled keys such as `filter[status]` and `ids[]` are expanded into objects and arrays
+#[inline]
+pub fn parse_query_string(input: &[u8], nested: bool) -> Map<String, Value> {
+    let mut map = Map::new();
+
+    for (key, value) in form_urlencoded::parse(input) {
+        let value = Value::String(value.into_owned());
+
+        if nested {
+            if let Some((base, segments)) = split_nested_key(&key) {
+                insert_nested(&mut map, base, &segments, value);
+                continue;
+            }
+        }
+
+        insert_multi_value(&mut map, key.into_owned(), value);
+    }
+
+    map
+}
+
+/// Keys are only expanded this many levels deep, the same as qs
+const MAX_NESTED_DEPTH: usize = 5;
+
+/// Splits `a[b][]` into `a` and `["b", ""]`, returns None if the key isn't in bracket form
+/// Anything past the maximum depth is kept as one literal segment, so `a[b][c][d][e][f][g]`
+/// ends with a `"[g]"` segment
+#[inline]
+fn split_nested_key(key: &str) -> Option<(&str, Vec<&str>)> {
+    let open = key.find('[')?;
+    if open == 0 {
+        return None;
+    }
+
+    let (base, mut rest) = key.split_at(open);
+    let mut segments = vec![];
+
+    while !rest.is_empty() {
+        if segments.len() == MAX_NESTED_DEPTH {
+            segments.push(rest);
+            break;
+        }
+
+        let inner = rest.strip_prefix('[')?;
+        let close = inner.find(']')?;
+
+        segments.push(&inner[..close]);
+        rest = &inner[close + 1..];
+    }
+
+    Some((base, segments))
+}
+
+#[inline]
+fn container_for(segment: &str) -> Value {
+    if segment.is_empty() {
+        Value::Array(vec![])
+    } else {
+        Value::Object(Map::new())
+    }
+}
+
+#[inline]
+fn insert_nested(map: &mut Map<String, Value>, base: &str, segments: &[&str], value: Value) {
+    let target = map
+        .entry(base.to_owned())
+        .or_insert_with(|| container_for(segments[0]));
+
+    insert_into(target, segments, value);
+}
+
+fn insert_into(target: &mut Value, segments: &[&str], value: Value) {
+    let (segment, rest) = match segments.split_first() {
+        Some(res) => res,
+        None => return,
+    };
+
+    // A key which was used both as a plain value and a container keeps the container
+    if segment.is_empty() && !target.is_array() {
+        *target = Value::Array(vec![]);
+    } else if !segment.is_empty() && !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+
+    match target {
+        Value::Array(items) => {
+            if rest.is_empty() {
+                items.push(value);
+                return;
+            }
+
+            let mut child = container_for(rest[0]);
+            insert_into(&mut child, rest, value);
+            items.push(child);
+        }
+        Value::Object(map) => {
+            if rest.is_empty() {
+                insert_multi_value(map, segment.to_string(), value);
+                return;
+            }
+
+            let child = map
+                .entry(segment.to_string())
+                .or_insert_with(|| container_for(rest[0]));
+
+            insert_into(child, rest, value);
+        }
+        _ => {}
+    }
+}
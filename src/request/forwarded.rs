@@ -0,0 +1,196 @@
+use std::{
+    cell::UnsafeCell,
+    net::{IpAddr, SocketAddr},
+};
+
+use actix_http::{HttpMessage, Request};
+use ipnet::IpNet;
+
+/// The address the client connected to, stored with each connection
+pub struct ConnectionDestination(pub SocketAddr);
+
+#[inline(always)]
+pub fn destination_addr(req: &Request) -> Option<SocketAddr> {
+    Some(req.conn_data::<ConnectionDestination>()?.0)
+}
+
+struct TrustedCell(UnsafeCell<Vec<IpNet>>);
+
+unsafe impl Sync for TrustedCell {}
+
+static TRUSTED_PROXIES: TrustedCell = TrustedCell(UnsafeCell::new(Vec::new()));
+
+/// Stores the trusted proxy ranges, this must only be called before the server starts
+#[cold]
+pub fn write_trusted_proxies(proxies: Vec<IpNet>) {
+    let trusted_ref = unsafe { &mut *TRUSTED_PROXIES.0.get() };
+    *trusted_ref = proxies;
+}
+
+#[inline(always)]
+fn get_trusted_proxies() -> &'static [IpNet] {
+    unsafe { &*TRUSTED_PROXIES.0.get() }
+}
+
+#[inline(always)]
+fn is_trusted(ip: IpAddr) -> bool {
+    let ip = ip.to_canonical();
+    get_trusted_proxies().iter().any(|net| net.contains(&ip))
+}
+
+/// A single hop added by a proxy, the address it received the request from
+/// and the scheme and host that request was made with
+#[derive(Default)]
+struct ForwardedHop {
+    for_ip: Option<IpAddr>,
+    proto: Option<String>,
+    host: Option<String>,
+}
+
+/// The client as seen by the first proxy we trust, falls back to the socket peer
+#[derive(Default)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub scheme: Option<String>,
+    pub host: Option<String>,
+}
+
+#[inline]
+fn header_list<'a>(req: &'a Request, name: &'static str) -> Vec<&'a str> {
+    req.headers()
+        .get_all(name)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(str::trim)
+        .filter(|val| !val.is_empty())
+        .collect()
+}
+
+/// Parses a node such as `192.0.2.43`, `"192.0.2.43:47011"` or `"[2001:db8::17]:4711"`
+/// obfuscated identifiers and `unknown` give None
+#[inline]
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+
+    if let Some(rest) = node.strip_prefix('[') {
+        let end = rest.find(']')?;
+        return rest[..end].parse().ok();
+    }
+
+    if let Ok(ip) = node.parse() {
+        return Some(ip);
+    }
+
+    let (ip, _port) = node.rsplit_once(':')?;
+    ip.parse().ok()
+}
+
+/// Parses the RFC 7239 `Forwarded` header into hops, in the order the proxies added them
+#[inline]
+fn parse_forwarded(req: &Request) -> Vec<ForwardedHop> {
+    header_list(req, "forwarded")
+        .into_iter()
+        .map(|element| {
+            let mut hop = ForwardedHop::default();
+
+            for pair in element.split(';') {
+                let (key, value) = match pair.split_once('=') {
+                    Some(res) => res,
+                    None => continue,
+                };
+
+                let value = value.trim().trim_matches('"');
+                match key.trim().to_ascii_lowercase().as_str() {
+                    "for" => hop.for_ip = parse_node(value),
+                    "proto" => hop.proto = Some(value.to_ascii_lowercase()),
+                    "host" => hop.host = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+
+            hop
+        })
+        .collect()
+}
+
+/// Builds hops from the `X-Forwarded-*` headers, proto and host lists are lined up with
+/// the addresses from the right when they have the same length, otherwise the last value is used
+/// as that is the one added by the nearest proxy, earlier values can be sent by the client
+#[inline]
+fn parse_x_forwarded(req: &Request) -> Vec<ForwardedHop> {
+    let addresses = header_list(req, "x-forwarded-for");
+    let protos = header_list(req, "x-forwarded-proto");
+    let hosts = header_list(req, "x-forwarded-host");
+
+    let pick = |list: &[&str], index: usize| {
+        let value = if list.len() == addresses.len() { list.get(index) } else { list.last() };
+        value.map(|val| val.to_string())
+    };
+
+    if addresses.is_empty() {
+        if protos.is_empty() && hosts.is_empty() {
+            return vec![];
+        }
+
+        return vec![ForwardedHop {
+            for_ip: None,
+            proto: protos.last().map(|val| val.to_ascii_lowercase()),
+            host: hosts.last().map(|val| val.to_string()),
+        }];
+    }
+
+    addresses
+        .iter()
+        .enumerate()
+        .map(|(index, address)| ForwardedHop {
+            for_ip: parse_node(address),
+            proto: pick(&protos, index).map(|val| val.to_ascii_lowercase()),
+            host: pick(&hosts, index),
+        })
+        .collect()
+}
+
+/// Works out the real client of the request. The forwarding headers are only read when the
+/// socket peer is a trusted proxy, the hops are then walked from the nearest proxy outwards
+/// until an address which isn't trusted is found
+#[inline]
+pub fn resolve_client(req: &Request) -> ClientInfo {
+    let peer = match req.peer_addr() {
+        Some(res) => res.ip(),
+        None => return ClientInfo::default(),
+    };
+
+    if !is_trusted(peer) {
+        return ClientInfo { ip: Some(peer), ..Default::default() };
+    }
+
+    let mut hops = parse_forwarded(req);
+    if hops.is_empty() {
+        hops = parse_x_forwarded(req);
+    }
+
+    let mut client = ClientInfo { ip: Some(peer), ..Default::default() };
+
+    for hop in hops.into_iter().rev() {
+        if hop.proto.is_some() {
+            client.scheme = hop.proto;
+        }
+
+        if hop.host.is_some() {
+            client.host = hop.host;
+        }
+
+        match hop.for_ip {
+            Some(ip) => {
+                client.ip = Some(ip);
+
+                if !is_trusted(ip) {
+                    break;
+                }
+            }
+            None => break,
+        }
+    }
+
+    client
+}
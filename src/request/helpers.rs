@@ -1,4 +1,9 @@
-use actix_http::{header::HeaderMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use actix_http::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, HOST},
+    HttpMessage, Request, Version,
+};
 use bytes::{BytesMut, Bytes, BufMut};
 use halfbrown::HashMap;
 use napi::{Error, Result, Status};
@@ -27,26 +32,90 @@ pub fn make_js_error_string(reason: String) -> Error {
     Error::new(Status::GenericFailure, reason)
 }
 
+#[cold]
+#[inline(never)]
+pub fn make_malformed_json_error(reason: String) -> Error {
+    Error::new(Status::InvalidArg, format!("Malformed JSON body: {}", reason))
+}
+
+static UNCHECKED_HEADERS: AtomicBool = AtomicBool::new(false);
+
+/// Skips validating response header values, this must only be called before the server starts
+#[cold]
+pub fn write_unchecked_headers(unchecked: bool) {
+    UNCHECKED_HEADERS.store(unchecked, Ordering::Relaxed);
+}
+
+#[cold]
+#[inline(never)]
+fn make_invalid_header_name_error(key: &[u8]) -> Error {
+    make_js_error_string(format!("Invalid header name: {}", String::from_utf8_lossy(key)))
+}
+
+#[cold]
+#[inline(never)]
+fn make_invalid_header_value_error(key: &HeaderName) -> Error {
+    make_js_error_string(format!("Invalid value for header: {}", key))
+}
+
 #[inline(always)]
-pub fn split_and_get_query_params(query_string: String) -> HalfBrown<String, String> {
-    let mut params = HashMap::with_capacity(16);
-    for pair in query_string.split('&') {
-        let mut split_two = pair.split('=');
-
-        let key = match split_two.next() {
-            Some(res) => res,
-            None => continue,
-        };
+pub fn to_header_name(key: &[u8]) -> Result<HeaderName> {
+    HeaderName::from_bytes(key).map_err(|_| make_invalid_header_name_error(key))
+}
 
-        let val = match split_two.next() {
-            Some(res) => res,
-            None => continue,
-        };
+/// Checks the value can be sent as a header, newlines and other control characters are rejected
+/// unless unchecked_headers was set in the server config
+#[inline(always)]
+pub fn to_header_value(key: &HeaderName, value: Bytes) -> Result<HeaderValue> {
+    if UNCHECKED_HEADERS.load(Ordering::Relaxed) {
+        return Ok(unsafe { HeaderValue::from_maybe_shared_unchecked(value) });
+    }
 
-        params.insert(key.to_owned(), val.to_owned());
+    HeaderValue::from_maybe_shared(value).map_err(|_| make_invalid_header_value_error(key))
+}
+
+#[inline(always)]
+pub fn is_json_content_type(headers: &HeaderMap) -> bool {
+    let content_type = match headers.get(CONTENT_TYPE).and_then(|val| val.to_str().ok()) {
+        Some(res) => res,
+        None => return false,
+    };
+
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.eq_ignore_ascii_case("application/json") || mime.ends_with("+json")
+}
+
+#[inline(always)]
+pub fn version_to_str(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_11 => "HTTP/1.1",
+        Version::HTTP_2 => "HTTP/2.0",
+        Version::HTTP_3 => "HTTP/3.0",
+        _ => "HTTP/1.1",
     }
+}
+
+/// The scheme the request was made with, the server only listens over plain TCP
+/// so this is http unless the request target was in absolute form
+#[inline(always)]
+pub fn request_scheme(req: &Request) -> &str {
+    req.uri().scheme_str().unwrap_or("http")
+}
+
+/// Rebuilds the absolute url of the request from the scheme, host and request target
+#[inline]
+pub fn request_full_url(req: &Request, scheme: &str, host: Option<&str>) -> String {
+    let uri = req.uri();
+    let host = host
+        .or_else(|| uri.authority().map(|authority| authority.as_str()))
+        .or_else(|| req.headers().get(HOST).and_then(|val| val.to_str().ok()))
+        .unwrap_or("localhost");
+
+    let path_and_query = uri.path_and_query().map_or("/", |path| path.as_str());
 
-    HalfBrown(params)
+    format!("{}://{}{}", scheme, host, path_and_query)
 }
 
 #[inline(always)]
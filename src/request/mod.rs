@@ -1,3 +1,6 @@
+pub mod cookies;
+pub mod form;
+pub mod forwarded;
 pub mod helpers;
 pub mod node_functions;
 pub mod send_resp;
@@ -1,11 +1,15 @@
 use std::mem::MaybeUninit;
 use actix_http::Request;
 use bytes::Bytes;
+use serde_json::Value;
 use tokio::sync::oneshot::Sender;
 use napi::Result;
 
-use super::helpers::make_js_error;
-use crate::response::{JsResponse, InnerResp};
+use super::{form::MultipartPart, helpers::make_js_error};
+use crate::{
+    response::{JsResponse, InnerResp, ResponseHeaders},
+    router::serializer::ResponseSerializer,
+};
 
 
 #[napi]
@@ -14,9 +18,12 @@ pub struct RequestBlob {
     pub(crate) oneshot: MaybeUninit<Sender<JsResponse>>,
     pub(crate) sent: bool,
     pub(crate) body: Option<Bytes>,
-    pub(crate) headers: MaybeUninit<Option<Vec<(Bytes, Bytes)>>>,
+    pub(crate) json_body: Option<Value>,
+    pub(crate) multipart: Option<Vec<MultipartPart>>,
+    pub(crate) headers: MaybeUninit<Option<ResponseHeaders>>,
     pub(crate) written: usize,
     pub(crate) status_code: Option<u16>,
+    pub(crate) serializer: Option<&'static ResponseSerializer>,
 }
 
 impl RequestBlob {
@@ -26,14 +33,25 @@ impl RequestBlob {
             oneshot: MaybeUninit::uninit(),
             sent: false,
             body: None,
+            json_body: None,
+            multipart: None,
             headers: MaybeUninit::uninit(),
             written: 0,
             status_code: None,
+            serializer: None,
         })
     }
     
     #[inline]
-    pub fn store_self_data(&mut self, data: Request, sender: Sender<JsResponse>, body: Option<Bytes>) {
+    pub fn store_self_data(
+        &mut self,
+        data: Request,
+        sender: Sender<JsResponse>,
+        body: Option<Bytes>,
+        json_body: Option<Value>,
+        multipart: Option<Vec<MultipartPart>>,
+        serializer: Option<&'static ResponseSerializer>,
+    ) {
         let oneshot = MaybeUninit::new(sender);
         let headers = MaybeUninit::new(None);
         let data = MaybeUninit::new(data);
@@ -46,9 +64,12 @@ impl RequestBlob {
         self.oneshot = oneshot;
         self.headers = headers;
         self.body = body;
+        self.json_body = json_body;
+        self.multipart = multipart;
         self.sent = false;
         self.written += 1;
         self.status_code = None;
+        self.serializer = serializer;
     }
 
     #[inline(always)]
@@ -1,13 +1,34 @@
-use actix_http::HttpMessage;
-use napi::bindgen_prelude::Uint8Array;
+use actix_http::{
+    header::{HOST, SET_COOKIE},
+    HttpMessage,
+};
+use bytes::Bytes;
+use napi::{
+    bindgen_prelude::Either,
+    Result,
+};
+use serde_json::{Map, Value};
 
 use crate::{
-    napi::{buff_str::BuffStr, fast_str::FastStr, halfbrown::HalfBrown},
+    napi::{
+        buff_str::BuffStr, external_bytes::ExternalBytes, fast_str::FastStr, halfbrown::HalfBrown,
+        json_value::JsonValue,
+    },
+    response::ResponseHeaders,
     router,
 };
 
 use super::{
-    helpers::{convert_header_map, split_and_get_query_params},
+    cookies::{build_set_cookie, decrypt, find_cookie, parse_cookies, verify_signed, CookieOptions},
+    form::{
+        insert_multi_value, is_urlencoded_content_type, parse_query_string, parse_urlencoded, parts_to_body,
+        MultipartBody,
+    },
+    forwarded::{destination_addr, resolve_client},
+    helpers::{
+        convert_header_map, is_json_content_type, make_js_error, make_malformed_json_error,
+        request_full_url, request_scheme, to_header_name, to_header_value, version_to_str,
+    },
     RequestBlob,
 };
 
@@ -16,18 +37,169 @@ impl RequestBlob {
     #[inline(always)]
     #[napi]
     /// Add a new header to the response sent to the user
-    pub fn add_header(&mut self, key: BuffStr, value: BuffStr) {
+    /// if the header has already been added the value is sent alongside the existing ones
+    /// Throws if the name or value is not a valid header
+    pub fn add_header(&mut self, key: BuffStr, value: BuffStr) -> Result<()> {
+        self.append_header(key, value)
+    }
+
+    #[inline(always)]
+    fn response_headers(&mut self) -> Option<&mut ResponseHeaders> {
         if self.sent {
-            return;
+            return None;
         }
 
         let headers = unsafe { self.headers.assume_init_mut() };
+        Some(headers.get_or_insert_with(ResponseHeaders::default))
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Add a header to the response, keeping any values already added with the same name
+    /// Throws if the name or value is not a valid header
+    pub fn append_header(&mut self, key: BuffStr, value: BuffStr) -> Result<()> {
+        let key = to_header_name(&key.0)?;
+        let value = to_header_value(&key, value.0)?;
+
+        if let Some(headers) = self.response_headers() {
+            headers.append(key, value);
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Set a header on the response, replacing any values already added with the same name
+    /// this can be used to override the default Server and Content-Type headers
+    /// Throws if the name or value is not a valid header
+    pub fn set_header(&mut self, key: BuffStr, value: BuffStr) -> Result<()> {
+        let key = to_header_name(&key.0)?;
+        let value = to_header_value(&key, value.0)?;
+
+        if let Some(headers) = self.response_headers() {
+            headers.set(key, value);
+        }
 
-        if let Some(list_of_headers) = headers {
-            list_of_headers.push((key.0, value.0))
-        } else {
-            *headers = Some(vec![(key.0, value.0)])
+        Ok(())
+    }
+
+    #[inline(always)]
+    #[napi(ts_args_type = "headers: Record<string, string | Array<string>>")]
+    /// Set multiple headers on the response, each replacing any values already added with the same name
+    /// an array of values sends the header once for each value
+    /// Throws if any name or value is not a valid header, in which case none of the headers are set
+    pub fn set_headers(&mut self, headers: HalfBrown<String, Either<String, Vec<String>>>) -> Result<()> {
+        let mut checked = Vec::with_capacity(headers.0.len());
+
+        for (key, value) in headers.0 {
+            let key = to_header_name(key.as_bytes())?;
+            let values = match value {
+                Either::A(value) => vec![to_header_value(&key, Bytes::from(value))?],
+                Either::B(values) => values
+                    .into_iter()
+                    .map(|value| to_header_value(&key, Bytes::from(value)))
+                    .collect::<Result<Vec<_>>>()?,
+            };
+
+            checked.push((key, values));
         }
+
+        if let Some(response_headers) = self.response_headers() {
+            for (key, values) in checked {
+                response_headers.remove(&key);
+
+                for value in values {
+                    response_headers.append(key.clone(), value);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Remove a header from the response, including the default Server and Content-Type headers
+    /// Throws if the name is not a valid header
+    pub fn remove_header(&mut self, key: BuffStr) -> Result<()> {
+        let key = to_header_name(&key.0)?;
+
+        if let Some(headers) = self.response_headers() {
+            headers.remove(&key);
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    #[napi(ts_return_type = "Record<string, string | Array<string>>")]
+    /// Get the headers which will be sent with the response, names are lowercased and
+    /// headers with multiple values have an array of values
+    pub fn get_response_headers(&self) -> JsonValue {
+        let mut map = Map::new();
+
+        if !self.sent {
+            let headers = unsafe { self.headers.assume_init_ref() };
+
+            for (key, value) in headers.iter().flat_map(|headers| headers.entries.iter()) {
+                let value = Value::String(String::from_utf8_lossy(value.as_bytes()).into_owned());
+                insert_multi_value(&mut map, key.to_string(), value);
+            }
+        }
+
+        JsonValue(Value::Object(map))
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the cookies sent with the request as an object with each name and decoded value
+    pub fn get_cookies(&self) -> HalfBrown<String, String> {
+        parse_cookies(self.get_data_val().headers())
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the decoded value of a cookie, this will be null if the cookie was not sent
+    pub fn get_cookie(&self, name: String) -> Option<String> {
+        Some(find_cookie(self.get_data_val().headers(), &name)?.value().to_string())
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the value of a cookie set with the signed option
+    /// this will be null if the cookie was not sent or the signature does not match
+    pub fn get_signed_cookie(&self, name: String) -> Result<Option<String>> {
+        match find_cookie(self.get_data_val().headers(), &name) {
+            Some(cookie) => verify_signed(cookie),
+            None => Ok(None),
+        }
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the value of a cookie set with the encrypted option
+    /// this will be null if the cookie was not sent or could not be decrypted
+    pub fn get_encrypted_cookie(&self, name: String) -> Result<Option<String>> {
+        match find_cookie(self.get_data_val().headers(), &name) {
+            Some(cookie) => decrypt(cookie),
+            None => Ok(None),
+        }
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Add a Set-Cookie header to the response, each cookie set is sent as its own header
+    /// Throws if the options are invalid or a signed or encrypted cookie is set without a cookie_secret
+    pub fn set_cookie(&mut self, name: String, value: String, options: Option<CookieOptions>) -> Result<()> {
+        let header = build_set_cookie(name, value, options)?;
+        let value = to_header_value(&SET_COOKIE, header)?;
+
+        if let Some(headers) = self.response_headers() {
+            headers.append(SET_COOKIE, value);
+        }
+
+        Ok(())
     }
 
     #[inline(always)]
@@ -48,13 +220,24 @@ impl RequestBlob {
         true
     }
 
+    #[inline(always)]
+    #[napi(ts_args_type = "nested?: boolean", ts_return_type = "Record<string, any> | null")]
+    /// Get the query parameters as an object with each key and the decoded value
+    /// Keys which are repeated will have an array of values
+    /// Passing true for nested expands keys like `filter[status]` and `ids[]` into objects and arrays
+    /// this will be null if there is no query string
+    pub fn get_query_params(&self, nested: Option<bool>) -> Option<JsonValue> {
+        let query_string = self.get_data_val().uri().query()?;
+        let parsed = parse_query_string(query_string.as_bytes(), nested.unwrap_or(false));
+
+        Some(JsonValue(parsed.into()))
+    }
+
     #[inline(always)]
     #[napi]
-    /// Get the query parameters as an object with each key and value
-    /// this will only be null if an error has occurred
-    pub fn get_query_params(&self) -> Option<HalfBrown<String, String>> {
-        let query_string = self.get_data_val().uri().query()?.to_owned();
-        Some(split_and_get_query_params(query_string))
+    /// Get the raw query string without the leading `?`, this will be null if there is no query string
+    pub fn get_query_string(&self) -> Option<String> {
+        Some(self.get_data_val().uri().query()?.to_owned())
     }
 
     #[inline(always)]
@@ -86,6 +269,19 @@ impl RequestBlob {
         Some(header_val.to_str().ok()?.to_string())
     }
 
+    #[inline(always)]
+    #[napi]
+    /// Get every value sent for a header, useful for headers which can be repeated
+    /// this will be empty if the header was not sent
+    pub fn get_header_values(&self, name: FastStr) -> Vec<String> {
+        self.get_data_val()
+            .headers()
+            .get_all(name.0)
+            .filter_map(|val| val.to_str().ok())
+            .map(|val| val.to_string())
+            .collect()
+    }
+
     #[inline(always)]
     #[napi]
     /// Get the url parameters as an object with each key and value
@@ -97,11 +293,163 @@ impl RequestBlob {
 
     #[inline(always)]
     #[napi]
-    /// Retrieve the raw body bytes in a Uint8Array to be used
-    pub fn get_body(&mut self) -> Uint8Array {
-        match &self.body {
-            Some(res) => res.clone().into(),
-            None => vec![].into(),
+    /// Get the HTTP method of the request e.g. GET
+    pub fn get_method(&self) -> String {
+        self.get_data_val().method().as_str().to_string()
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the path of the request without the query string
+    pub fn get_path(&self) -> String {
+        self.get_data_val().path().to_string()
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the full url of the request including the scheme, host and query string
+    /// when the request came through a trusted proxy the forwarded scheme and host are used
+    pub fn get_url(&self) -> String {
+        let req = self.get_data_val();
+        let client = resolve_client(req);
+        let scheme = client.scheme.as_deref().unwrap_or_else(|| request_scheme(req));
+
+        request_full_url(req, scheme, client.host.as_deref())
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the HTTP version of the request e.g. HTTP/1.1
+    pub fn get_http_version(&self) -> String {
+        version_to_str(self.get_data_val().version()).to_string()
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the scheme the request was made with e.g. http
+    pub fn get_scheme(&self) -> String {
+        request_scheme(self.get_data_val()).to_string()
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the address of the socket which made the request as ip:port
+    /// this will be null if the address is not known
+    pub fn get_peer_address(&self) -> Option<String> {
+        Some(self.get_data_val().peer_addr()?.to_string())
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the ip address of the socket which made the request
+    /// this will be null if the address is not known
+    pub fn get_peer_ip(&self) -> Option<String> {
+        Some(self.get_data_val().peer_addr()?.ip().to_string())
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the address the client connected to as ip:port, for PROXY protocol listeners
+    /// this is the destination sent by the proxy. This will be null if the address is not known
+    pub fn get_destination_address(&self) -> Option<String> {
+        Some(destination_addr(self.get_data_val())?.to_string())
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the ip address of the client, when the request came through a trusted proxy
+    /// this is read from the Forwarded or X-Forwarded-For headers otherwise it is the peer ip
+    /// this will be null if the address is not known
+    pub fn get_client_ip(&self) -> Option<String> {
+        Some(resolve_client(self.get_data_val()).ip?.to_string())
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the scheme the client used, when the request came through a trusted proxy
+    /// this is read from the Forwarded or X-Forwarded-Proto headers
+    pub fn get_client_scheme(&self) -> String {
+        let req = self.get_data_val();
+        match resolve_client(req).scheme {
+            Some(res) => res,
+            None => request_scheme(req).to_string(),
+        }
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Get the host the client requested, when the request came through a trusted proxy
+    /// this is read from the Forwarded or X-Forwarded-Host headers otherwise the Host header is used
+    /// this will be null if no host was sent
+    pub fn get_client_host(&self) -> Option<String> {
+        let req = self.get_data_val();
+        match resolve_client(req).host {
+            Some(res) => Some(res),
+            None => Some(req.headers().get(HOST)?.to_str().ok()?.to_string()),
         }
     }
+
+    #[inline(always)]
+    #[napi(ts_return_type = "Uint8Array")]
+    /// Retrieve a copy of the body bytes in a Uint8Array, gzip, deflate and br bodies are already decoded
+    pub fn get_body(&self) -> ExternalBytes {
+        ExternalBytes::copied(self.body.clone().unwrap_or_default())
+    }
+
+    #[inline(always)]
+    #[napi(ts_return_type = "Uint8Array")]
+    /// Retrieve the body bytes without copying, the Uint8Array shares memory with the server
+    /// The body is handed over to JS so getBody, getJsonBody and getFormData won't see it afterwards
+    pub fn get_body_no_copy(&mut self) -> ExternalBytes {
+        ExternalBytes::external(self.body.take().unwrap_or_default())
+    }
+
+    #[inline(always)]
+    #[napi(ts_return_type = "any")]
+    /// Retrieve the body parsed as JSON, this will be null if there is no body
+    /// Routes registered with the jsonBody option have this parsed on the worker thread before the handler is called
+    /// Throws if the content type is not application/json and an InvalidArg error if the body is malformed
+    pub fn get_json_body(&mut self) -> Result<Option<JsonValue>> {
+        if let Some(parsed) = self.json_body.take() {
+            return Ok(Some(JsonValue(parsed)));
+        }
+
+        let body = match &self.body {
+            Some(res) if !res.is_empty() => res,
+            _ => return Ok(None),
+        };
+
+        if !is_json_content_type(self.get_data_val().headers()) {
+            return Err(make_js_error("Expected an application/json content type."));
+        }
+
+        let parsed = serde_json::from_slice(body).map_err(|e| make_malformed_json_error(e.to_string()))?;
+        Ok(Some(JsonValue(parsed)))
+    }
+
+    #[inline(always)]
+    #[napi(ts_return_type = "Record<string, string | string[]> | null")]
+    /// Retrieve an application/x-www-form-urlencoded body as an object, keys which are repeated
+    /// will have an array of values. This will be null if there is no body
+    pub fn get_form_data(&self) -> Result<Option<JsonValue>> {
+        let body = match &self.body {
+            Some(res) if !res.is_empty() => res,
+            _ => return Ok(None),
+        };
+
+        if !is_urlencoded_content_type(self.get_data_val().headers()) {
+            return Err(make_js_error("Expected an application/x-www-form-urlencoded body."));
+        }
+
+        Ok(Some(JsonValue(parse_urlencoded(body).into())))
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// Retrieve the fields and files from a multipart/form-data body, the route needs to be
+    /// registered with the multipart option. This will be null if the body was not multipart
+    /// The parts can only be retrieved once
+    pub fn get_multipart(&mut self) -> Option<MultipartBody> {
+        self.multipart.take().map(parts_to_body)
+    }
 }
@@ -1,12 +1,51 @@
-use napi::Result;
-use serde_json::Value;
+use actix_http::header::CONTENT_TYPE;
+use bytes::Bytes;
+use http::HeaderValue;
+use napi::{bindgen_prelude::Either, Env, JsUnknown, Result};
 
 use crate::{
-    napi::{buff_str::BuffStr, bytes_recv::JsBytes, fast_serde::FasterValue, fast_str::FastStr},
-    response::InnerResp,
+    files::send::{FileResponse, SendFileOptions},
+    napi::{buff_str::BuffStr, bytes_recv::{JsBytes, SharedBytes}, fast_str::FastStr, halfbrown::HalfBrown, json_writer::write_json},
+    response::{
+        prepared::PreparedResponse,
+        sniff::sniff_content_type,
+        stream::{response_stream, ResponseStream},
+        InnerResp, RAW_HEADER_VAL,
+    },
 };
 
-use super::{helpers::value_to_bytes, RequestBlob};
+use super::{
+    helpers::{make_js_error, make_js_error_string, to_header_value},
+    RequestBlob,
+};
+
+/// Uses the content type given, otherwise it is sniffed from the data
+#[inline(always)]
+fn binary_content_type(data: &Bytes, content_type: Option<BuffStr>) -> Result<HeaderValue> {
+    if let Some(content_type) = content_type {
+        return to_header_value(&CONTENT_TYPE, content_type.0);
+    }
+
+    match sniff_content_type(data) {
+        Some(res) => Ok(HeaderValue::from_static(res)),
+        None => Ok(RAW_HEADER_VAL.clone()),
+    }
+}
+
+impl RequestBlob {
+    /// Uses the route's compiled serializer if it has a response schema
+    #[inline(always)]
+    fn to_json(&self, env: Env, response: JsUnknown) -> Result<Bytes> {
+        let value = response.0.value;
+
+        unsafe {
+            match self.serializer {
+                Some(serializer) => serializer.write(env.raw(), value),
+                None => write_json(env.raw(), value),
+            }
+        }
+    }
+}
 
 #[napi]
 impl RequestBlob {
@@ -48,6 +87,88 @@ impl RequestBlob {
         let _ = self.send_result_checked(message, false);
     }
 
+    #[inline(always)]
+    #[napi(ts_args_type = "response: Uint8Array, contentType?: string")]
+    /// This needs to be called at the end of every request even if nothing is returned
+    /// This sends binary data to the client, if no content type is given it is worked out from
+    /// the first bytes of the data falling back to application/octet-stream
+    pub fn send_bytes(&mut self, response: JsBytes, content_type: Option<BuffStr>) -> Result<()> {
+        let content_type = binary_content_type(&response.0, content_type)?;
+        let message = InnerResp::Raw(response.0, content_type);
+        self.send_result(message)
+    }
+
+    #[inline(always)]
+    #[napi(ts_args_type = "response: Uint8Array, contentType?: string")]
+    /// This needs to be called at the end of every request even if nothing is returned
+    /// The same as sendBytes but arrays of 4KB or more are sent without being copied. The array must
+    /// not be written to, detached or transferred until the response has been sent, so don't use
+    /// this with pooled or reused buffers
+    pub fn send_bytes_no_copy(&mut self, response: SharedBytes, content_type: Option<BuffStr>) -> Result<()> {
+        let content_type = binary_content_type(&response.0, content_type)?;
+        let message = InnerResp::Raw(response.0, content_type);
+        self.send_result(message)
+    }
+
+    #[inline(always)]
+    #[napi(ts_args_type = "response: Buffer, contentType?: string")]
+    /// An alias of sendBytes for Node Buffers
+    pub fn send_buffer(&mut self, response: JsBytes, content_type: Option<BuffStr>) -> Result<()> {
+        self.send_bytes(response, content_type)
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// This needs to be called at the end of every request even if nothing is returned
+    /// This streams a file from disk on the worker thread so it is never loaded into JS
+    /// Conditional and range requests are handled, a missing file is sent as a 404
+    /// Headers set on this request are sent but the status code comes from the file
+    pub fn send_file(&mut self, path: String, options: Option<SendFileOptions>) -> Result<()> {
+        let message = InnerResp::File(Box::new(FileResponse::new(path, options)?));
+        self.send_result(message)
+    }
+
+    #[inline(always)]
+    #[napi(ts_args_type = "status?: number, headers?: Record<string, string | Array<string>>")]
+    /// This can be called in place of the other send methods to send the body a chunk at a time
+    /// The status and headers are sent straight away, the body is sent with chunked transfer encoding
+    /// and finishes when `end` is called on the returned stream. The content type defaults to application/octet-stream
+    /// Throws if the status code or any header is invalid
+    pub fn start_stream(
+        &mut self,
+        status: Option<u16>,
+        headers: Option<HalfBrown<String, Either<String, Vec<String>>>>,
+    ) -> Result<ResponseStream> {
+        if self.sent {
+            return Err(make_js_error("Already sent response."));
+        }
+
+        if let Some(status) = status {
+            if !self.set_status_code(status) {
+                return Err(make_js_error_string(format!("Invalid status code: {}", status)));
+            }
+        }
+
+        if let Some(headers) = headers {
+            self.set_headers(headers)?;
+        }
+
+        let (writer, body) = response_stream();
+        self.send_result(InnerResp::Stream(body))?;
+
+        Ok(writer)
+    }
+
+    #[inline(always)]
+    #[napi]
+    /// This needs to be called at the end of every request even if nothing is returned
+    /// This sends a response built with `prepareResponse`, the status and headers it was
+    /// prepared with are used in place of any set on this request
+    pub fn send_prepared(&mut self, response: &PreparedResponse) -> Result<()> {
+        let message = InnerResp::Prepared(response.inner.clone());
+        self.send_result(message)
+    }
+
     #[inline(always)]
     #[napi]
     /// This needs to be called at the end of every request even if nothing is returned
@@ -71,8 +192,10 @@ impl RequestBlob {
     #[napi(ts_args_type = "response: any")]
     /// This needs to be called at the end of every request even if nothing is returned
     /// This will send a JSON object to client, it will be serialized rust side so no need to stringify
-    pub fn send_object(&mut self, response: Value) -> Result<()> {
-        let message = InnerResp::Json(value_to_bytes(response)?);
+    /// Values are serialized the same as JSON.stringify, toJSON is called, Buffers are sent as base64 and circular objects throw
+    /// If the route has a response schema only the properties it describes are sent
+    pub fn send_object(&mut self, env: Env, response: JsUnknown) -> Result<()> {
+        let message = InnerResp::Json(self.to_json(env, response)?);
         self.send_result(message)
     }
 
@@ -80,9 +203,9 @@ impl RequestBlob {
     #[napi(ts_args_type = "response: Object")]
     /// This needs to be called at the end of every request even if nothing is returned
     /// This will send a JSON object to client, it will be serialized rust side so no need to stringify
-    /// This needs to be a key value object, any other is undefined behaviour
-    pub fn send_fast_object(&mut self, response: FasterValue) -> Result<()> {
-        let message = InnerResp::Json(value_to_bytes(response.0)?);
+    /// This is the same as sendObject
+    pub fn send_fast_object(&mut self, env: Env, response: JsUnknown) -> Result<()> {
+        let message = InnerResp::Json(self.to_json(env, response)?);
         self.send_result(message)
     }
 
@@ -90,11 +213,10 @@ impl RequestBlob {
     #[napi(ts_args_type = "response: Object")]
     /// This needs to be called at the end of every request even if nothing is returned
     /// This will send a JSON object to client, it will be serialized rust side so no need to stringify
-    /// This needs to be a key value object, any other is undefined behaviour
     /// This method will not check if a previous response has been sent doing so will result in undefined behavior but will be faster
     /// The return value will only indicate if the message was sent or not
-    pub fn send_fast_object_unchecked(&mut self, response: FasterValue) -> bool {
-        let value = match value_to_bytes(response.0) {
+    pub fn send_fast_object_unchecked(&mut self, env: Env, response: JsUnknown) -> bool {
+        let value = match self.to_json(env, response) {
             Ok(value) => value,
             Err(_) => return false,
         };
@@ -0,0 +1,246 @@
+use std::cell::UnsafeCell;
+
+use actix_http::{
+    body::{BodySize, BoxBody, MessageBody},
+    encoding::Encoder,
+    header::{
+        ContentEncoding, HeaderMap, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_RANGE, CONTENT_TYPE, ETAG, VARY,
+    },
+    HttpMessage, Method, Request, Response, StatusCode,
+};
+use halfbrown::HashMap;
+use http::HeaderValue;
+use napi::Result;
+
+use crate::request::helpers::{make_js_error, make_js_error_string};
+
+const DEFAULT_MIN_SIZE: usize = 1024;
+
+/// Content types ending in a slash match every subtype
+const DEFAULT_TYPES: &[&str] = &[
+    "text/",
+    "application/json",
+    "application/javascript",
+    "application/xml",
+    "image/svg+xml",
+];
+
+static VARY_ACCEPT_ENCODING: HeaderValue = HeaderValue::from_static("accept-encoding");
+
+/// The compression settings from the server config, no encodings means compression is off
+#[derive(Clone, Debug, Default)]
+pub struct CompressionSettings {
+    /// The enabled encodings in the order the server prefers them
+    pub encodings: Vec<ContentEncoding>,
+    pub min_size: usize,
+    pub types: Vec<String>,
+}
+
+impl CompressionSettings {
+    /// Reads the compression keys from the server config
+    #[cold]
+    pub fn from_config(config: &HashMap<String, String>) -> Result<Self> {
+        let encodings = match config.get("compression") {
+            Some(res) => parse_encodings(res)?,
+            None => return Ok(Self::default()),
+        };
+
+        let min_size = match config.get("compression_min_size") {
+            Some(res) => res
+                .parse::<usize>()
+                .map_err(|_| make_js_error("Invalid number provided for compression_min_size"))?,
+            None => DEFAULT_MIN_SIZE,
+        };
+
+        let types = match config.get("compression_types") {
+            Some(res) => split_list(res).map(str::to_ascii_lowercase).collect(),
+            None => DEFAULT_TYPES.iter().map(|content_type| content_type.to_string()).collect(),
+        };
+
+        Ok(Self { encodings, min_size, types })
+    }
+
+    #[inline]
+    fn allows_type(&self, headers: &HeaderMap) -> bool {
+        let content_type = match headers.get(CONTENT_TYPE).and_then(|val| val.to_str().ok()) {
+            Some(res) => res,
+            None => return false,
+        };
+
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+
+        self.types.iter().any(|allowed| {
+            if allowed.ends_with('/') {
+                essence.len() > allowed.len() && essence[..allowed.len()].eq_ignore_ascii_case(allowed)
+            } else {
+                essence.eq_ignore_ascii_case(allowed)
+            }
+        })
+    }
+}
+
+#[inline]
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|entry| !entry.is_empty())
+}
+
+#[cold]
+fn parse_encodings(list: &str) -> Result<Vec<ContentEncoding>> {
+    split_list(list)
+        .map(|entry| match entry.to_ascii_lowercase().as_str() {
+            "br" | "brotli" => Ok(ContentEncoding::Brotli),
+            "gzip" => Ok(ContentEncoding::Gzip),
+            "zstd" => Ok(ContentEncoding::Zstd),
+            _ => Err(make_js_error_string(format!("Invalid compression encoding provided: {}", entry))),
+        })
+        .collect()
+}
+
+struct CompressionCell(UnsafeCell<CompressionSettings>);
+
+unsafe impl Sync for CompressionCell {}
+
+static COMPRESSION: CompressionCell = CompressionCell(UnsafeCell::new(CompressionSettings {
+    encodings: Vec::new(),
+    min_size: DEFAULT_MIN_SIZE,
+    types: Vec::new(),
+}));
+
+/// Stores the compression settings, this must only be called before the server starts
+#[cold]
+pub fn write_compression(settings: CompressionSettings) {
+    let settings_ref = unsafe { &mut *COMPRESSION.0.get() };
+    *settings_ref = settings;
+}
+
+#[inline(always)]
+fn get_compression() -> &'static CompressionSettings {
+    unsafe { &*COMPRESSION.0.get() }
+}
+
+/// Picks the enabled encoding with the highest q-value, ties go to the encoding listed first
+/// in the server config. Identity is used when the client accepts none of them
+fn negotiate(accept: Option<&HeaderValue>, enabled: &[ContentEncoding]) -> ContentEncoding {
+    let header = match accept.and_then(|val| val.to_str().ok()) {
+        Some(res) => res,
+        None => return ContentEncoding::Identity,
+    };
+
+    let mut wildcard = None;
+    let mut qualities = vec![None; enabled.len()];
+
+    for item in split_list(header) {
+        let mut params = item.split(';');
+        let name = params.next().unwrap_or_default().trim();
+        let quality = params
+            .find_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                key.trim().eq_ignore_ascii_case("q").then(|| value.trim())
+            })
+            .map_or(1.0, |value| value.parse::<f32>().unwrap_or(0.0));
+
+        if name == "*" {
+            wildcard = Some(quality);
+        } else if let Some(index) = enabled.iter().position(|encoding| encoding.as_str().eq_ignore_ascii_case(name)) {
+            qualities[index] = Some(quality);
+        }
+    }
+
+    let mut best = (ContentEncoding::Identity, 0.0);
+    for (encoding, quality) in enabled.iter().zip(qualities) {
+        let quality = quality.or(wildcard).unwrap_or(0.0);
+        if quality > best.1 {
+            best = (*encoding, quality);
+        }
+    }
+
+    best.0
+}
+
+#[inline]
+fn varies_on_encoding(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(VARY)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(str::trim)
+        .any(|val| val == "*" || val.eq_ignore_ascii_case("accept-encoding"))
+}
+
+/// The encoded body isn't byte for byte the same as the one the validator was made for, so a strong
+/// ETag is made weak. Conditional requests still match since If-None-Match compares tags weakly
+#[inline]
+fn weaken_etag(headers: &mut HeaderMap) {
+    let weak = match headers.get(ETAG) {
+        Some(etag) if !etag.as_bytes().starts_with(b"W/") => {
+            let mut weak = Vec::with_capacity(etag.len() + 2);
+            weak.extend_from_slice(b"W/");
+            weak.extend_from_slice(etag.as_bytes());
+
+            HeaderValue::from_bytes(&weak)
+        }
+        _ => return,
+    };
+
+    if let Ok(weak) = weak {
+        headers.insert(ETAG, weak);
+    }
+}
+
+#[inline]
+fn should_compress(req: &Request, rsp: &Response<BoxBody>, settings: &CompressionSettings) -> bool {
+    let size = match rsp.body().size() {
+        BodySize::Sized(size) => size as usize,
+        _ => return false,
+    };
+
+    let headers = rsp.headers();
+
+    size >= settings.min_size
+        && req.method() != Method::HEAD
+        && !matches!(
+            rsp.status(),
+            StatusCode::SWITCHING_PROTOCOLS | StatusCode::NO_CONTENT | StatusCode::PARTIAL_CONTENT | StatusCode::NOT_MODIFIED
+        )
+        && !headers.contains_key(CONTENT_ENCODING)
+        && !headers.contains_key(CONTENT_RANGE)
+        && settings.allows_type(headers)
+}
+
+/// Compresses the response body with the best encoding the client accepts, this runs on the
+/// worker thread and larger bodies are compressed on its blocking pool
+/// Only sized bodies are compressed so streams and event streams are always sent as they are
+pub fn compress_response(req: &Request, mut rsp: Response<BoxBody>) -> Response<BoxBody> {
+    let settings = get_compression();
+    if settings.encodings.is_empty() || !should_compress(req, &rsp, settings) {
+        return rsp;
+    }
+
+    let already_varies = varies_on_encoding(rsp.headers());
+    let encoding = negotiate(req.headers().get(ACCEPT_ENCODING), &settings.encodings);
+
+    if encoding == ContentEncoding::Identity {
+        if !already_varies {
+            rsp.headers_mut().append(VARY, VARY_ACCEPT_ENCODING.clone());
+        }
+
+        return rsp;
+    }
+
+    let vary: Vec<HeaderValue> = rsp.headers().get_all(VARY).cloned().collect();
+    let mut rsp = rsp.map_body(|head, body| Encoder::response(encoding, head, body));
+
+    let hdrs = rsp.headers_mut();
+    weaken_etag(hdrs);
+
+    // The encoder always adds its own Vary value, the handler's one is enough
+    if already_varies {
+        hdrs.remove(VARY);
+
+        for value in vary {
+            hdrs.append(VARY, value);
+        }
+    }
+
+    rsp.map_into_boxed_body()
+}
@@ -1,17 +1,29 @@
 use actix_http::{
-    header::{HeaderMap, HeaderName, CONTENT_TYPE, SERVER},
-    Response, StatusCode,
+    body::BoxBody,
+    header::{HeaderMap, HeaderName, CACHE_CONTROL, CONTENT_TYPE, SERVER},
+    Request, Response, StatusCode,
 };
+use std::sync::Arc;
+
 use bytes::Bytes;
 use http::HeaderValue;
 
-use crate::templates::store_in_bytes_buffer;
+use crate::{files::send::FileResponse, sse::body::SseBody, templates::store_in_bytes_buffer};
+
+use self::{prepared::PreparedBody, stream::StreamBody};
+
+pub mod compress;
+pub mod prepared;
+pub mod sniff;
+pub mod stream;
 
-static WALKER_SERVER: HeaderValue = HeaderValue::from_static("walker");
+pub static WALKER_SERVER: HeaderValue = HeaderValue::from_static("walker");
 
-static TEXT_HEADER_VAL: HeaderValue = HeaderValue::from_static("text/plain; charset=UTF-8");
+pub static TEXT_HEADER_VAL: HeaderValue = HeaderValue::from_static("text/plain; charset=UTF-8");
 static JSON_HEADER_VAL: HeaderValue = HeaderValue::from_static("application/json; charset=UTF-8");
-static RAW_HEADER_VAL: HeaderValue = HeaderValue::from_static("application/octet-stream; charset=UTF-8");
+pub static RAW_HEADER_VAL: HeaderValue = HeaderValue::from_static("application/octet-stream");
+static EVENT_STREAM_HEADER_VAL: HeaderValue = HeaderValue::from_static("text/event-stream");
+static NO_CACHE_HEADER_VAL: HeaderValue = HeaderValue::from_static("no-cache");
 static HTML_HEADER_VAL: HeaderValue = HeaderValue::from_static("text/html; charset=UTF-8");
 
 static INTERNAL_SERVER_ERROR: Bytes = Bytes::from_static(b"Internal Server Error");
@@ -19,17 +31,53 @@ static INTERNAL_SERVER_ERROR: Bytes = Bytes::from_static(b"Internal Server Error
 pub struct JsResponse {
     pub inner: InnerResp,
     pub status_code: Option<u16>,
-    pub headers: Option<Vec<(Bytes, Bytes)>>,
+    pub headers: Option<ResponseHeaders>,
+}
+
+/// The headers set by the handler, values are kept in the order they were added so repeated
+/// names are all sent. Removed names are tracked so the default headers can be dropped too
+#[derive(Default)]
+pub struct ResponseHeaders {
+    pub entries: Vec<(HeaderName, HeaderValue)>,
+    removed: Vec<HeaderName>,
+}
+
+impl ResponseHeaders {
+    #[inline(always)]
+    pub fn append(&mut self, key: HeaderName, value: HeaderValue) {
+        self.entries.push((key, value));
+    }
+
+    #[inline]
+    pub fn set(&mut self, key: HeaderName, value: HeaderValue) {
+        self.remove(&key);
+        self.entries.push((key, value));
+    }
+
+    #[inline]
+    pub fn remove(&mut self, key: &HeaderName) {
+        self.entries.retain(|(name, _)| name != key);
+
+        if !self.removed.contains(key) {
+            self.removed.push(key.clone());
+        }
+    }
 }
 
 pub enum InnerResp {
     Text(Bytes),
     Json(Bytes),
-    Raw(Bytes),
+    Raw(Bytes, HeaderValue),
     Template(String, String, String),
     ServerError,
     ServerErrorWithMessage(Bytes),
     EmptyString,
+    Prepared(Arc<PreparedBody>),
+    File(Box<FileResponse>),
+    Stream(StreamBody),
+    Sse(SseBody),
+    /// Accepts a WebSocket upgrade, the frames sent to the socket are written to this body
+    WebSocket(StreamBody),
 }
 
 use InnerResp::*;
@@ -65,26 +113,49 @@ fn render_internal_error_with_bytes(message: Bytes) -> Response<Bytes> {
 fn apply_headers(
     hdrs: &mut HeaderMap,
     content_header: HeaderValue,
-    headers: Option<Vec<(Bytes, Bytes)>>,
+    headers: Option<ResponseHeaders>,
 ) {
-    hdrs.insert(SERVER, WALKER_SERVER.clone());
-    hdrs.insert(CONTENT_TYPE, content_header);
-
-    if let Some(custom_headers) = headers {
-        for (key_b, val_b) in custom_headers {
-            let key = match HeaderName::from_bytes(&key_b) {
-                Ok(res) => res,
-                Err(e) => {
-                    println!("Error {:?}", e);
-                    continue;
-                }
-            };
-
-            unsafe {
-                let value = HeaderValue::from_maybe_shared_unchecked(val_b);
-                hdrs.insert(key, value);
-            }
+    let ResponseHeaders { entries, removed } = match headers {
+        Some(res) => res,
+        None => {
+            hdrs.insert(SERVER, WALKER_SERVER.clone());
+            hdrs.insert(CONTENT_TYPE, content_header);
+            return;
         }
+    };
+
+    for (key, value) in entries {
+        hdrs.append(key, value);
+    }
+
+    // The defaults are only used when the handler hasn't set or removed them
+    if !hdrs.contains_key(SERVER) && !removed.contains(&SERVER) {
+        hdrs.insert(SERVER, WALKER_SERVER.clone());
+    }
+
+    if !hdrs.contains_key(CONTENT_TYPE) && !removed.contains(&CONTENT_TYPE) {
+        hdrs.insert(CONTENT_TYPE, content_header);
+    }
+}
+
+/// Headers from the handler replace any of the same name already on the response
+#[inline]
+pub fn override_headers(hdrs: &mut HeaderMap, headers: Option<ResponseHeaders>) {
+    let ResponseHeaders { entries, removed } = match headers {
+        Some(res) => res,
+        None => return,
+    };
+
+    for key in &removed {
+        hdrs.remove(key);
+    }
+
+    for (key, _) in &entries {
+        hdrs.remove(key);
+    }
+
+    for (key, value) in entries {
+        hdrs.append(key, value);
     }
 }
 
@@ -108,14 +179,17 @@ impl JsResponse {
         let message = match &self.inner {
             Text(_) | EmptyString => TEXT_HEADER_VAL.clone(),
             Json(_) => JSON_HEADER_VAL.clone(),
-            Raw(_) => RAW_HEADER_VAL.clone(),
+            Raw(_, content_type) => content_type.clone(),
             Template(_, _, _) => HTML_HEADER_VAL.clone(),
             ServerError => return render_internal_error(),
             ServerErrorWithMessage(message) => return render_internal_error_with_bytes(message.clone()),
+            Prepared(prepared) => return prepared.to_response(),
+            // Files and streams are sent from into_response
+            File(_) | Stream(_) | Sse(_) | WebSocket(_) => return render_internal_error(),
         };
 
         let bytes = match self.inner {
-            Text(message) | Json(message) | Raw(message) => message,
+            Text(message) | Json(message) | Raw(message, _) => message,
             Template(group, file, context) => {
                 let buffer = match store_in_bytes_buffer(&group, &file, &context) {
                     Ok(res) => res,
@@ -134,4 +208,37 @@ impl JsResponse {
 
         rsp
     }
+
+    /// Builds the response, files need the request to answer conditional and range requests
+    #[inline(always)]
+    pub async fn into_response(self, req: &Request) -> Response<BoxBody> {
+        let JsResponse { inner, status_code, headers } = self;
+
+        match inner {
+            File(file) => {
+                let mut rsp = file.send(req).await;
+                override_headers(rsp.headers_mut(), headers);
+
+                rsp
+            }
+            Stream(body) => {
+                let mut rsp = Response::with_body(Self::get_status_code(status_code), body).map_into_boxed_body();
+                apply_headers(rsp.headers_mut(), RAW_HEADER_VAL.clone(), headers);
+
+                rsp
+            }
+            Sse(body) => {
+                let mut rsp = Response::with_body(StatusCode::OK, body).map_into_boxed_body();
+                let hdrs = rsp.headers_mut();
+
+                apply_headers(hdrs, EVENT_STREAM_HEADER_VAL.clone(), headers);
+                if !hdrs.contains_key(CACHE_CONTROL) {
+                    hdrs.insert(CACHE_CONTROL, NO_CACHE_HEADER_VAL.clone());
+                }
+
+                rsp
+            }
+            inner => JsResponse { inner, status_code, headers }.apply_to_response().map_into_boxed_body(),
+        }
+    }
 }
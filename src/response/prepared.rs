@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use actix_http::{
+    header::{HeaderMap, CONTENT_TYPE, SERVER},
+    Response, StatusCode,
+};
+use bytes::Bytes;
+use http::HeaderValue;
+use napi::{
+    bindgen_prelude::{Buffer, Either},
+    Result,
+};
+
+use crate::{
+    napi::halfbrown::HalfBrown,
+    request::helpers::{make_js_error_string, to_header_name, to_header_value},
+};
+
+use super::{sniff::sniff_content_type, RAW_HEADER_VAL, TEXT_HEADER_VAL, WALKER_SERVER};
+
+/// The parts of a response which never change
+#[napi(object)]
+pub struct PrepareResponseOptions {
+    /// Strings are sent as text/plain and buffers have their content type sniffed unless one is given
+    pub body: Either<String, Buffer>,
+    /// Defaults to 200
+    pub status: Option<u16>,
+    #[napi(ts_type = "Record<string, string | Array<string>>")]
+    pub headers: Option<HalfBrown<String, Either<String, Vec<String>>>>,
+    pub content_type: Option<String>,
+}
+
+/// A response built ahead of time, each send only clones the body and headers
+pub struct PreparedBody {
+    body: Bytes,
+    status: StatusCode,
+    headers: HeaderMap,
+}
+
+impl PreparedBody {
+    /// Validates everything up front so sending can never fail
+    #[cold]
+    pub fn build(options: PrepareResponseOptions) -> Result<Self> {
+        let status = options.status.unwrap_or(200);
+        let status = StatusCode::from_u16(status)
+            .map_err(|_| make_js_error_string(format!("Invalid status code: {}", status)))?;
+
+        let (body, default_type) = match options.body {
+            Either::A(text) => (Bytes::from(text), TEXT_HEADER_VAL.clone()),
+            Either::B(buffer) => {
+                let body = Bytes::copy_from_slice(&buffer);
+                let content_type = match sniff_content_type(&body) {
+                    Some(res) => HeaderValue::from_static(res),
+                    None => RAW_HEADER_VAL.clone(),
+                };
+
+                (body, content_type)
+            }
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert(SERVER, WALKER_SERVER.clone());
+
+        let content_type = match options.content_type {
+            Some(res) => to_header_value(&CONTENT_TYPE, Bytes::from(res))?,
+            None => default_type,
+        };
+        headers.insert(CONTENT_TYPE, content_type);
+
+        for (key, value) in options.headers.map(|headers| headers.0).unwrap_or_default() {
+            let key = to_header_name(key.as_bytes())?;
+            headers.remove(&key);
+
+            let values = match value {
+                Either::A(value) => vec![value],
+                Either::B(values) => values,
+            };
+
+            for value in values {
+                let value = to_header_value(&key, Bytes::from(value))?;
+                headers.append(key.clone(), value);
+            }
+        }
+
+        Ok(Self { body, status, headers })
+    }
+
+    #[inline(always)]
+    pub fn to_response(&self) -> Response<Bytes> {
+        let mut rsp = Response::with_body(self.status, self.body.clone());
+        *rsp.headers_mut() = self.headers.clone();
+
+        rsp
+    }
+}
+
+#[napi]
+/// A handle to a response built with `prepareResponse`, send it with `sendPrepared`
+pub struct PreparedResponse {
+    pub(crate) inner: Arc<PreparedBody>,
+}
+
+#[cold]
+#[napi]
+/// Builds a response once so it can be sent many times with `sendPrepared`
+/// without converting the body or checking the headers again
+/// Throws if the status code or any header is invalid
+pub fn prepare_response(options: PrepareResponseOptions) -> Result<PreparedResponse> {
+    let inner = PreparedBody::build(options)?;
+
+    Ok(PreparedResponse { inner: Arc::new(inner) })
+}
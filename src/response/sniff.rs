@@ -0,0 +1,66 @@
+/// The signatures we check for, the offset is where the magic bytes start
+static SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xFF\xD8\xFF", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (0, b"\x00\x00\x01\x00", "image/x-icon"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"\x1F\x8B", "application/gzip"),
+    (0, b"\x00asm", "application/wasm"),
+    (0, b"wOFF", "font/woff"),
+    (0, b"wOF2", "font/woff2"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"ID3", "audio/mpeg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"\x1A\x45\xDF\xA3", "video/webm"),
+    (4, b"ftyp", "video/mp4"),
+];
+
+/// RIFF containers share a prefix, the format is at offset 8
+static RIFF_FORMATS: &[(&[u8], &str)] = &[
+    (b"WEBP", "image/webp"),
+    (b"WAVE", "audio/wav"),
+    (b"AVI ", "video/x-msvideo"),
+];
+
+/// The size of a BITMAPFILEHEADER plus the smallest info header
+const BMP_MIN_HEADER: usize = 26;
+
+/// "BM" is too short to trust on its own, so the file header also has to give the length of the
+/// data, have zeroed reserved fields and point at pixel data after the headers
+#[inline]
+fn is_bmp(data: &[u8]) -> bool {
+    if data.len() < BMP_MIN_HEADER || !data.starts_with(b"BM") {
+        return false;
+    }
+
+    let read_u32 = |offset: usize| u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+
+    read_u32(2) as usize == data.len()
+        && data[6..10] == [0, 0, 0, 0]
+        && (BMP_MIN_HEADER..data.len()).contains(&(read_u32(10) as usize))
+}
+
+/// Works out the content type from the first few bytes of the data, this will be None if
+/// nothing matched in which case the data should be sent as application/octet-stream
+#[inline]
+pub fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"RIFF") && data.len() >= 12 {
+        let format = &data[8..12];
+        return RIFF_FORMATS
+            .iter()
+            .find(|(magic, _)| *magic == format)
+            .map(|(_, content_type)| *content_type);
+    }
+
+    if is_bmp(data) {
+        return Some("image/bmp");
+    }
+
+    SIGNATURES
+        .iter()
+        .find(|(offset, magic, _)| data.get(*offset..).is_some_and(|rest| rest.starts_with(magic)))
+        .map(|(_, _, content_type)| *content_type)
+}
@@ -0,0 +1,299 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use actix_http::body::{BodySize, MessageBody};
+use bytes::Bytes;
+use napi::{
+    bindgen_prelude::FromNapiValue,
+    check_status,
+    sys::{self, napi_env, napi_value},
+    Env, Error, JsObject, Result, ValueType,
+};
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    Notify,
+};
+
+use crate::{
+    napi::{buff_str::BuffStr, bytes_recv::JsBytes},
+    request::helpers::make_js_error,
+    tokio_workers,
+};
+
+/// Writes wait once this many bytes are queued and not yet sent to the client
+const HIGH_WATER_MARK: usize = 64 * 1024;
+
+/// State shared between the JS writer and the body being sent by actix
+struct StreamShared {
+    queued: AtomicUsize,
+    closed: AtomicBool,
+    /// Set when the connection should be dropped without sending the queued chunks
+    aborted: AtomicBool,
+    drained: Notify,
+}
+
+/// A response body made of chunks written from JS, sent with chunked transfer encoding.
+/// An empty chunk ends the body so copies of the sender held elsewhere can't keep it open
+pub struct StreamBody {
+    receiver: UnboundedReceiver<Bytes>,
+    shared: Arc<StreamShared>,
+    finished: bool,
+}
+
+impl MessageBody for StreamBody {
+    type Error = io::Error;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<io::Result<Bytes>>> {
+        let this = &mut *self;
+        if this.finished {
+            return Poll::Ready(None);
+        }
+
+        if this.shared.aborted.load(Ordering::Acquire) {
+            this.finished = true;
+            return Poll::Ready(Some(Err(io::Error::other("The stream was aborted"))));
+        }
+
+        match this.receiver.poll_recv(cx) {
+            Poll::Ready(Some(chunk)) if chunk.is_empty() => {
+                this.finished = true;
+                Poll::Ready(None)
+            }
+            Poll::Ready(Some(chunk)) => {
+                this.shared.queued.fetch_sub(chunk.len(), Ordering::AcqRel);
+                this.shared.drained.notify_waiters();
+
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// The body is dropped once the response is finished or the client has gone away
+impl Drop for StreamBody {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.shared.drained.notify_waiters();
+    }
+}
+
+/// A chunk written to a stream, either a string or a Uint8Array
+pub struct StreamChunk(Bytes);
+
+impl FromNapiValue for StreamChunk {
+    #[inline(always)]
+    unsafe fn from_napi_value(env: napi_env, napi_val: napi_value) -> Result<Self> {
+        let mut value_type = 0;
+        check_status!(sys::napi_typeof(env, napi_val, &mut value_type), "Failed to get the type of the chunk")?;
+
+        if ValueType::from(value_type) == ValueType::String {
+            return Ok(Self(BuffStr::from_napi_value(env, napi_val)?.0));
+        }
+
+        Ok(Self(JsBytes::from_napi_value(env, napi_val)?.0))
+    }
+}
+
+/// Sends chunks to a stream body, copies of this are used to send to a stream outside of JS
+#[derive(Clone)]
+pub struct ChunkSender {
+    sender: UnboundedSender<Bytes>,
+    shared: Arc<StreamShared>,
+}
+
+impl ChunkSender {
+    /// Returns false once the body has been dropped
+    #[inline]
+    pub fn send(&self, chunk: Bytes) -> bool {
+        let len = chunk.len();
+        self.shared.queued.fetch_add(len, Ordering::AcqRel);
+
+        if self.sender.send(chunk).is_err() {
+            self.shared.queued.fetch_sub(len, Ordering::AcqRel);
+            return false;
+        }
+
+        true
+    }
+
+    /// Ends the body, chunks already sent are still delivered
+    #[inline]
+    pub fn finish(&self) {
+        let _ = self.sender.send(Bytes::new());
+    }
+
+    /// Drops the connection, the chunks still queued are discarded
+    #[inline]
+    pub fn abort(&self) {
+        self.shared.aborted.store(true, Ordering::Release);
+        // Wakes the body so it sees the flag
+        let _ = self.sender.send(Bytes::new());
+    }
+
+    /// The bytes sent but not yet written to the connection
+    #[inline]
+    pub fn queued(&self) -> usize {
+        self.shared.queued.load(Ordering::Acquire)
+    }
+}
+
+#[napi]
+/// Writes a response to the client a chunk at a time, returned from `startStream`
+pub struct ResponseStream {
+    sender: Option<ChunkSender>,
+    shared: Arc<StreamShared>,
+}
+
+/// Creates a body and the sender which feeds it
+#[inline]
+pub fn chunk_channel() -> (ChunkSender, StreamBody) {
+    let (sender, receiver) = unbounded_channel();
+    let shared = Arc::new(StreamShared {
+        queued: AtomicUsize::new(0),
+        closed: AtomicBool::new(false),
+        aborted: AtomicBool::new(false),
+        drained: Notify::new(),
+    });
+
+    let sender = ChunkSender { sender, shared: shared.clone() };
+    let body = StreamBody { receiver, shared, finished: false };
+
+    (sender, body)
+}
+
+/// Creates the JS writer and the body it feeds
+#[inline]
+pub fn response_stream() -> (ResponseStream, StreamBody) {
+    let (sender, body) = chunk_channel();
+    let writer = ResponseStream { shared: sender.shared.clone(), sender: Some(sender) };
+
+    (writer, body)
+}
+
+#[inline(always)]
+fn resolve_empty(_: Env) -> Result<()> {
+    Ok(())
+}
+
+/// Waits until the queued chunks are below the high water mark, fails if the client disconnects first
+async fn wait_for_drain(shared: Arc<StreamShared>) -> Result<()> {
+    loop {
+        let notified = shared.drained.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+
+        if shared.closed.load(Ordering::Acquire) {
+            return Err(Error::from_reason("The client disconnected."));
+        }
+
+        if shared.queued.load(Ordering::Acquire) <= HIGH_WATER_MARK {
+            return Ok(());
+        }
+
+        notified.await;
+    }
+}
+
+impl ResponseStream {
+    #[inline]
+    pub(crate) fn push(&self, chunk: Bytes) -> Result<()> {
+        let sender = match &self.sender {
+            Some(res) => res,
+            None => return Err(make_js_error("Stream already ended.")),
+        };
+
+        if chunk.is_empty() || sender.send(chunk) {
+            return Ok(());
+        }
+
+        Err(make_js_error("The client disconnected."))
+    }
+
+    /// Another sender for the same body, it stops working once the stream has ended
+    #[inline]
+    pub(crate) fn sender(&self) -> Option<ChunkSender> {
+        self.sender.clone()
+    }
+
+    /// Ends the body even when other senders are still alive
+    #[inline]
+    pub(crate) fn finish(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            sender.finish();
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_ended(&self) -> bool {
+        self.sender.is_none() || self.shared.closed.load(Ordering::Acquire)
+    }
+
+    /// Waits for the queued chunks to drain the same as `write`
+    pub(crate) fn drained_promise(&self, env: Env) -> Result<JsObject> {
+        let (deferred, promise) = env.create_deferred()?;
+
+        if self.shared.queued.load(Ordering::Acquire) <= HIGH_WATER_MARK {
+            deferred.resolve(resolve_empty);
+            return Ok(promise);
+        }
+
+        let shared = self.shared.clone();
+        tokio_workers::spawn(async move {
+            match wait_for_drain(shared).await {
+                Ok(()) => deferred.resolve(resolve_empty),
+                Err(e) => deferred.reject(e),
+            }
+        });
+
+        Ok(promise)
+    }
+}
+
+#[napi]
+impl ResponseStream {
+    #[napi(ts_args_type = "chunk: string | Uint8Array", ts_return_type = "Promise<void>")]
+    /// Queues a chunk to be sent, chunks are always sent in the order they are written
+    /// The promise resolves once there is room for more data and rejects if the client has disconnected
+    pub fn write(&self, env: Env, chunk: StreamChunk) -> Result<JsObject> {
+        if let Err(e) = self.push(chunk.0) {
+            let (deferred, promise) = env.create_deferred::<(), fn(Env) -> Result<()>>()?;
+            deferred.reject(e);
+
+            return Ok(promise);
+        }
+
+        self.drained_promise(env)
+    }
+
+    #[napi(ts_args_type = "chunk?: string | Uint8Array")]
+    /// Sends an optional last chunk and finishes the response
+    /// Calling this again without a chunk does nothing
+    pub fn end(&mut self, chunk: Option<StreamChunk>) -> Result<()> {
+        if let Some(chunk) = chunk {
+            self.push(chunk.0)?;
+        }
+
+        self.finish();
+        Ok(())
+    }
+
+    #[napi]
+    /// True once the client has disconnected or the response has been fully sent
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::Acquire)
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use crate::{response::prepared::PreparedBody, sse::stream::SseRoute, types::CallBackFunction, ws::route::WsRoute};
+
+use super::{route_info::MultipartOptions, serializer::ResponseSerializer, validation::RouteValidator};
+
+const DEFAULT_MAX_PART_SIZE: u64 = 1_048_576; // 1mb per part
+const DEFAULT_MAX_BODY_SIZE: u64 = 16_777_216; // 16mb for the whole body
+
+/// The limits used when streaming a multipart body for a route
+#[derive(Clone)]
+pub struct MultipartLimits {
+  pub max_part_size: u64,
+  pub max_body_size: u64,
+  pub max_file_size: u64,
+  pub file_memory_limit: Option<u64>,
+  pub temp_dir: Option<String>,
+}
+
+impl From<&MultipartOptions> for MultipartLimits {
+  #[cold]
+  fn from(options: &MultipartOptions) -> Self {
+    let max_body_size = options.max_body_size.map_or(DEFAULT_MAX_BODY_SIZE, u64::from);
+
+    Self {
+      max_part_size: options.max_part_size.map_or(DEFAULT_MAX_PART_SIZE, u64::from),
+      max_body_size,
+      max_file_size: options.max_file_size.map_or(max_body_size, u64::from),
+      file_memory_limit: options.file_memory_limit.map(u64::from),
+      temp_dir: options.temp_dir.clone(),
+    }
+  }
+}
+
+/// What answers a route, static routes are sent from Rust without calling into JS
+#[derive(Clone)]
+pub enum RouteHandler {
+  Js(CallBackFunction),
+  Static(Arc<PreparedBody>),
+  Sse(Arc<SseRoute>),
+  Ws(Arc<WsRoute>),
+}
+
+/// The value stored in the router for each registered path
+#[derive(Clone)]
+pub struct RouteEntry {
+  pub handler: RouteHandler,
+  pub validator: Option<Arc<RouteValidator>>,
+  pub parse_json: bool,
+  pub multipart: Option<MultipartLimits>,
+  pub serializer: Option<Arc<ResponseSerializer>>,
+  pub compress: bool,
+}
+
+impl RouteEntry {
+  #[inline(always)]
+  pub fn reads_body(&self) -> bool {
+    self.parse_json
+  }
+}
@@ -1,3 +1,8 @@
+pub mod entry;
 pub mod node_functions;
+pub mod openapi;
 pub mod read_only;
-pub mod store;
\ No newline at end of file
+pub mod route_info;
+pub mod serializer;
+pub mod store;
+pub mod validation;
\ No newline at end of file
@@ -1,7 +1,21 @@
+use std::sync::Arc;
+
 use actix_http::Method;
 use napi::bindgen_prelude::*;
+use serde_json::Value;
 
-use crate::{router::store::add_new_route, napi::tsfn::ThreadsafeFunction};
+use crate::{
+  napi::tsfn::ThreadsafeFunction,
+  response::prepared::{PrepareResponseOptions, PreparedBody},
+  router::{
+    entry::{RouteEntry, RouteHandler},
+    openapi::build_openapi_document,
+    route_info::{RouteInfo, RouteOptions},
+    serializer::ResponseSerializer,
+    store::{add_new_route, registered_routes},
+    validation::RouteValidator,
+  },
+};
 
 #[napi]
 /// The different HTTP methods 
@@ -37,51 +51,121 @@ impl Methods {
         _ => None
     }
   }
+
+  #[inline(always)]
+  pub fn as_str(&self) -> &'static str {
+    match self {
+        Methods::GET => "GET",
+        Methods::POST => "POST",
+        Methods::PUT => "PUT",
+        Methods::PATCH => "PATCH",
+        Methods::DELETE => "DELETE",
+    }
+  }
 }
 
 #[cold]
-#[napi(ts_args_type = "route: string, method: Methods, callback: (result: RequestBlob) => void")]
+#[napi(ts_args_type = "route: string, method: Methods, callback: (result: RequestBlob) => void, options?: RouteOptions")]
 /// Use this to register a new route in the server, the callback function will be called
 /// once the endpoint has been hit. The callback includes a RequestBlob which has all the methods
 /// needed to get the information from the request
-pub fn new_route(route: String, method: Methods, callback: JsFunction) -> Result<()> {
+/// If the options include schemas for the params, query or body the request is validated before
+/// the callback is called, invalid requests are rejected with a 400
+/// A 2xx response schema is compiled into a JSON writer used by `sendObject` for this route
+pub fn new_route(route: String, method: Methods, callback: JsFunction, options: Option<RouteOptions>) -> Result<()> {
+  let options = options.unwrap_or_default();
+  let validator = RouteValidator::from_schema(options.schema.as_ref())?;
+
+  let parse_json = options.json_body.unwrap_or(false)
+    || validator.as_ref().is_some_and(|validator| validator.needs_body());
+
+  let serializer = ResponseSerializer::from_schema(callback.0.env, options.schema.as_ref())?;
+
   let tsfn = ThreadsafeFunction::create(callback.0.env, callback.0.value, 1024)?;
+  let entry = RouteEntry {
+    handler: RouteHandler::Js(tsfn),
+    validator: validator.map(Arc::new),
+    parse_json,
+    multipart: options.multipart.as_ref().map(Into::into),
+    serializer: serializer.map(Arc::new),
+    compress: options.compress.unwrap_or(true),
+  };
 
-  add_new_route(&route, method, tsfn)
+  add_new_route(&route, method, entry, options)
 }
 
 #[cold]
-#[napi(ts_args_type = "route: string, callback: (result: RequestBlob) => void")]
+#[napi(ts_args_type = "route: string, callback: (result: RequestBlob) => void, options?: RouteOptions")]
 /// Adds a handler for the a GET request
 /// once the endpoint has been hit. The callback includes a RequestBlob which has all the methods
 /// needed to get the information from the request
-pub fn get(route: String, callback: JsFunction) -> Result<()> {
-  new_route(route, Methods::GET, callback)
+pub fn get(route: String, callback: JsFunction, options: Option<RouteOptions>) -> Result<()> {
+  new_route(route, Methods::GET, callback, options)
 }
 
 #[cold]
-#[napi(ts_args_type = "route: string, callback: (result: RequestBlob) => void")]
+#[napi(ts_args_type = "route: string, callback: (result: RequestBlob) => void, options?: RouteOptions")]
 /// Adds a handler for the a POST request
 /// once the endpoint has been hit. The callback includes a RequestBlob which has all the methods
 /// needed to get the information from the request
-pub fn post(route: String, callback: JsFunction) -> Result<()> {
-  new_route(route, Methods::POST, callback)
+pub fn post(route: String, callback: JsFunction, options: Option<RouteOptions>) -> Result<()> {
+  new_route(route, Methods::POST, callback, options)
 }
 
 #[cold]
-#[napi(ts_args_type = "route: string, callback: (result: RequestBlob) => void")]
+#[napi(ts_args_type = "route: string, callback: (result: RequestBlob) => void, options?: RouteOptions")]
 /// Adds a handler for the a PUT request
 /// once the endpoint has been hit. The callback includes a RequestBlob which has all the methods
 /// needed to get the information from the request
-pub fn put(route: String, callback: JsFunction) -> Result<()> {
-  new_route(route, Methods::PUT, callback)
+pub fn put(route: String, callback: JsFunction, options: Option<RouteOptions>) -> Result<()> {
+  new_route(route, Methods::PUT, callback, options)
 }
 
 #[cold]
-#[napi(ts_args_type = "route: string, callback: (result: RequestBlob) => void")]
+#[napi(ts_args_type = "route: string, callback: (result: RequestBlob) => void, options?: RouteOptions")]
 /// Adds a handler for the a PATCH request
 /// once the endpoint has been hit. The callback includes a RequestBlob which has all the methods
 /// needed to get the information from the request
-pub fn patch(route: String, callback: JsFunction) -> Result<()> {
-  new_route(route, Methods::PATCH, callback)
+pub fn patch(route: String, callback: JsFunction, options: Option<RouteOptions>) -> Result<()> {
+  new_route(route, Methods::PATCH, callback, options)
+}
+
+#[cold]
+#[napi(ts_args_type = "method: Methods, route: string, response: PrepareResponseOptions")]
+/// Adds a route which always sends the same response, it is answered on the worker thread
+/// without calling into JS so it keeps responding while the JS thread is busy
+/// Throws if the status code or any header is invalid
+pub fn static_route(method: Methods, route: String, response: PrepareResponseOptions) -> Result<()> {
+  let prepared = PreparedBody::build(response)?;
+
+  let entry = RouteEntry {
+    handler: RouteHandler::Static(Arc::new(prepared)),
+    validator: None,
+    parse_json: false,
+    multipart: None,
+    serializer: None,
+    compress: false,
+  };
+
+  add_new_route(&route, method, entry, RouteOptions::default())
+}
+
+#[cold]
+#[napi]
+/// Returns every route registered so far in the order they were added
+/// Each entry includes the method, the path pattern, the name and the options it was registered with
+pub fn list_routes() -> Vec<RouteInfo> {
+  registered_routes()
+}
+
+#[cold]
+#[napi(ts_args_type = "title?: string, version?: string", ts_return_type = "any")]
+/// Generates an OpenAPI 3.1 document from the registered routes and the schemas
+/// attached to them when they were registered
+pub fn open_api_document(title: Option<String>, version: Option<String>) -> Value {
+  build_openapi_document(
+    &registered_routes(),
+    title.as_deref().unwrap_or("Walker"),
+    version.as_deref().unwrap_or("1.0.0"),
+  )
 }
@@ -0,0 +1,168 @@
+use serde_json::{json, Map, Value};
+
+use super::route_info::{RouteInfo, RouteSchema};
+
+/// Converts a matchit route such as `/users/:id/*rest` into the OpenAPI form `/users/{id}/{rest}`
+/// returning the names of the path parameters found along the way
+#[cold]
+pub fn to_openapi_path(route: &str) -> (String, Vec<String>) {
+  let mut path = String::with_capacity(route.len() + 4);
+  let mut params = Vec::new();
+  let mut chars = route.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    if c != ':' && c != '*' {
+      path.push(c);
+      continue;
+    }
+
+    let mut name = String::new();
+    while let Some(next) = chars.peek() {
+      if *next == '/' {
+        break;
+      }
+
+      name.push(*next);
+      chars.next();
+    }
+
+    path.push('{');
+    path.push_str(&name);
+    path.push('}');
+    params.push(name);
+  }
+
+  (path, params)
+}
+
+#[inline]
+fn property_schema(schema: Option<&Value>, name: &str) -> Value {
+  schema
+    .and_then(|schema| schema.get("properties"))
+    .and_then(|properties| properties.get(name))
+    .cloned()
+    .unwrap_or_else(|| json!({ "type": "string" }))
+}
+
+#[inline]
+fn is_required(schema: &Value, name: &str) -> bool {
+  match schema.get("required").and_then(Value::as_array) {
+    Some(required) => required.iter().any(|entry| entry.as_str() == Some(name)),
+    None => false,
+  }
+}
+
+#[cold]
+fn build_parameters(path_params: &[String], schema: Option<&RouteSchema>) -> Vec<Value> {
+  let mut parameters = Vec::with_capacity(path_params.len());
+
+  let params_schema = schema.and_then(|schema| schema.params.as_ref());
+  for name in path_params {
+    parameters.push(json!({
+      "name": name,
+      "in": "path",
+      "required": true,
+      "schema": property_schema(params_schema, name),
+    }));
+  }
+
+  let query_schema = match schema.and_then(|schema| schema.query.as_ref()) {
+    Some(res) => res,
+    None => return parameters,
+  };
+
+  if let Some(properties) = query_schema.get("properties").and_then(Value::as_object) {
+    for (name, property) in properties {
+      parameters.push(json!({
+        "name": name,
+        "in": "query",
+        "required": is_required(query_schema, name),
+        "schema": property,
+      }));
+    }
+  }
+
+  parameters
+}
+
+/// Responses can either be given as a full OpenAPI response object or as a JSON schema,
+/// in the latter case it is wrapped up as a JSON response
+#[cold]
+fn build_responses(schema: Option<&RouteSchema>) -> Value {
+  let responses = match schema.and_then(|schema| schema.responses.as_ref()).and_then(Value::as_object) {
+    Some(res) => res,
+    None => return json!({ "200": { "description": "Successful response" } }),
+  };
+
+  let mut built = Map::with_capacity(responses.len());
+  for (status, response) in responses {
+    let is_response_object = response.get("description").is_some() || response.get("content").is_some();
+
+    let value = if is_response_object {
+      response.clone()
+    } else {
+      json!({
+        "description": format!("{} response", status),
+        "content": { "application/json": { "schema": response } },
+      })
+    };
+
+    built.insert(status.clone(), value);
+  }
+
+  Value::Object(built)
+}
+
+#[cold]
+fn build_operation(route: &RouteInfo, path_params: &[String]) -> Value {
+  let schema = route.options.schema.as_ref();
+  let mut operation = Map::new();
+
+  if let Some(name) = &route.name {
+    operation.insert("operationId".to_string(), Value::String(name.clone()));
+  }
+
+  let parameters = build_parameters(path_params, schema);
+  if !parameters.is_empty() {
+    operation.insert("parameters".to_string(), Value::Array(parameters));
+  }
+
+  if let Some(body) = schema.and_then(|schema| schema.body.as_ref()) {
+    operation.insert(
+      "requestBody".to_string(),
+      json!({
+        "required": true,
+        "content": { "application/json": { "schema": body } },
+      }),
+    );
+  }
+
+  operation.insert("responses".to_string(), build_responses(schema));
+
+  Value::Object(operation)
+}
+
+/// Builds an OpenAPI 3.1 document describing every route passed in
+#[cold]
+pub fn build_openapi_document(routes: &[RouteInfo], title: &str, version: &str) -> Value {
+  let mut paths = Map::new();
+
+  for route in routes {
+    let (path, path_params) = to_openapi_path(&route.path);
+    let operation = build_operation(route, &path_params);
+
+    let entry = paths
+      .entry(path)
+      .or_insert_with(|| Value::Object(Map::new()));
+
+    if let Value::Object(methods) = entry {
+      methods.insert(route.method.to_lowercase(), operation);
+    }
+  }
+
+  json!({
+    "openapi": "3.1.0",
+    "info": { "title": title, "version": version },
+    "paths": paths,
+  })
+}
@@ -4,13 +4,15 @@ use actix_http::Method;
 use halfbrown::HashMap;
 use matchit::{Router, Params};
 
-use crate::{types::CallBackFunction, napi::halfbrown::HalfBrown};
+use crate::napi::halfbrown::HalfBrown;
+
+use super::entry::RouteEntry;
 
 struct RouteCell(UnsafeCell<MaybeUninit<ReadRoutes>>);
 
 unsafe impl Sync for RouteCell where ReadRoutes: Sync {}
 
-type ReaderLookup = Router<CallBackFunction>;
+type ReaderLookup = Router<RouteEntry>;
 static ROUTER: RouteCell = RouteCell(UnsafeCell::new(MaybeUninit::uninit()));
 
 pub struct ReadRoutes {
@@ -47,7 +49,7 @@ fn get_routers() -> &'static ReadRoutes {
 }
 
 #[inline(always)]
-pub fn get_route(route: &str, method: Method) -> Option<&'static CallBackFunction> {
+pub fn get_route(route: &str, method: Method) -> Option<&'static RouteEntry> {
   let checking = get_routers().get_for_actix_method(method)?;
   let found = checking.at(route);
 
@@ -0,0 +1,79 @@
+use std::fmt::Write;
+
+use bytes::Bytes;
+use serde_json::Value;
+
+/// JSON schemas describing the inputs and outputs of a route
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct RouteSchema {
+  /// An object schema whose properties describe the url parameters
+  pub params: Option<Value>,
+  /// An object schema whose properties describe the query parameters
+  pub query: Option<Value>,
+  /// The schema of the JSON request body
+  pub body: Option<Value>,
+  /// An object keyed by status code, each value is either a schema or an OpenAPI response object
+  /// The first 2xx schema is used to serialize objects sent with `sendObject`, only the properties it lists are sent
+  pub responses: Option<Value>,
+}
+
+/// Limits for routes which accept multipart/form-data bodies
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct MultipartOptions {
+  /// The largest size in bytes allowed for a single part kept in memory, defaults to 1MB
+  pub max_part_size: Option<u32>,
+  /// The largest size in bytes allowed for the whole body, defaults to 16MB
+  pub max_body_size: Option<u32>,
+  /// The largest size in bytes allowed for a file part written to a temporary file, defaults to the max body size
+  pub max_file_size: Option<u32>,
+  /// File parts larger than this many bytes are written to a temporary file instead of kept in memory,
+  /// the files are removed once the response has been sent or the client disconnects
+  pub file_memory_limit: Option<u32>,
+  /// The directory temporary files are written to, defaults to the OS temp directory
+  pub temp_dir: Option<String>,
+}
+
+/// Optional settings that can be attached to a route when it is registered
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct RouteOptions {
+  /// A name used to identify the route, this is shown when listing routes
+  pub name: Option<String>,
+  /// Schemas for the route, these are used when generating the OpenAPI document
+  /// and to validate requests before they reach JS
+  pub schema: Option<RouteSchema>,
+  /// Parse the body as JSON on the worker thread before calling the handler,
+  /// malformed bodies are rejected with a 400 and other content types with a 415
+  pub json_body: Option<bool>,
+  /// Stream multipart/form-data bodies into parts on the worker thread,
+  /// these can then be read with `getMultipart`
+  pub multipart: Option<MultipartOptions>,
+  /// Compress responses with the encodings enabled in the server config, defaults to true
+  /// Set this to false for responses which are already compressed
+  pub compress: Option<bool>,
+}
+
+/// Information about a registered route, returned from `listRoutes`
+#[napi(object)]
+#[derive(Clone)]
+pub struct RouteInfo {
+  pub method: String,
+  pub path: String,
+  pub name: Option<String>,
+  pub options: RouteOptions,
+}
+
+#[cold]
+pub fn format_route_table(routes: &[RouteInfo]) -> Bytes {
+  let path_width = routes.iter().map(|route| route.path.len()).max().unwrap_or(0);
+  let mut table = String::with_capacity(routes.len() * (path_width + 24));
+
+  for route in routes {
+    let name = route.name.as_deref().unwrap_or("-");
+    let _ = writeln!(table, "{:<8}{:<width$}  {}", route.method, route.path, name, width = path_width);
+  }
+
+  Bytes::from(table)
+}
@@ -0,0 +1,281 @@
+use std::ptr;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use napi::{
+  check_status,
+  sys::{self, napi_env, napi_value},
+  Result,
+};
+use serde_json::Value;
+
+use crate::napi::json_writer::{write_escaped, JsonKey, JsonWriter};
+
+use super::route_info::RouteSchema;
+
+/// How a value is written, anything the schema doesn't describe as an object
+/// or array is written by its actual type
+enum Plan {
+  Any,
+  Object(Vec<Field>),
+  Array(Box<Plan>),
+}
+
+struct Field {
+  /// Where the property key is in the keys array
+  key_index: u32,
+  /// The escaped key with a leading comma e.g. `,"name":`
+  prefix: Bytes,
+  plan: Plan,
+}
+
+/// The property keys as a JS array held by a reference, so lookups
+/// use the same strings every time instead of creating new ones
+struct KeysRef(sys::napi_ref);
+
+unsafe impl Send for KeysRef {}
+unsafe impl Sync for KeysRef {}
+
+/// A JSON writer built from the response schema of a route. Only the properties named in the
+/// schema are read and written, objects it describes don't have toJSON called on them
+pub struct ResponseSerializer {
+  plan: Plan,
+  keys: KeysRef,
+  key_count: u32,
+}
+
+#[inline]
+fn has_type(schema: &Value, kind: &str) -> bool {
+  match schema.get("type") {
+    Some(Value::String(res)) => res == kind,
+    Some(Value::Array(types)) => types.iter().any(|entry| entry.as_str() == Some(kind)),
+    _ => false,
+  }
+}
+
+/// Picks the schema of the first successful response, unwrapping OpenAPI response objects
+#[cold]
+fn success_schema(schema: Option<&RouteSchema>) -> Option<&Value> {
+  let responses = schema?.responses.as_ref()?.as_object()?;
+  let (_, response) = responses.iter().find(|(status, _)| status.starts_with('2'))?;
+
+  let is_response_object = response.get("description").is_some() || response.get("content").is_some();
+  if !is_response_object {
+    return Some(response);
+  }
+
+  response.get("content")?.get("application/json")?.get("schema")
+}
+
+#[cold]
+fn compile_plan(schema: &Value, keys: &mut Vec<String>) -> Plan {
+  if has_type(schema, "object") {
+    let properties = match schema.get("properties").and_then(Value::as_object) {
+      Some(res) => res,
+      None => return Plan::Any,
+    };
+
+    let fields = properties
+      .iter()
+      .map(|(name, property)| {
+        let mut prefix = BytesMut::with_capacity(name.len() + 4);
+        prefix.put_u8(b',');
+        write_escaped(&mut prefix, name.as_bytes());
+        prefix.put_u8(b':');
+
+        keys.push(name.clone());
+
+        Field {
+          key_index: (keys.len() - 1) as u32,
+          prefix: prefix.freeze(),
+          plan: compile_plan(property, keys),
+        }
+      })
+      .collect();
+
+    return Plan::Object(fields);
+  }
+
+  if has_type(schema, "array") {
+    if let Some(items) = schema.get("items").filter(|items| items.is_object()) {
+      return Plan::Array(Box::new(compile_plan(items, keys)));
+    }
+  }
+
+  Plan::Any
+}
+
+#[cold]
+unsafe fn create_keys_ref(env: napi_env, keys: &[String]) -> Result<sys::napi_ref> {
+  let mut array = ptr::null_mut();
+  check_status!(sys::napi_create_array_with_length(env, keys.len(), &mut array), "Failed to create array")?;
+
+  for (index, key) in keys.iter().enumerate() {
+    let mut js_key = ptr::null_mut();
+    check_status!(
+      sys::napi_create_string_utf8(env, key.as_ptr() as *const _, key.len(), &mut js_key),
+      "Failed to create napi `string`",
+    )?;
+    check_status!(sys::napi_set_element(env, array, index as u32, js_key), "Failed to set element")?;
+  }
+
+  let mut reference = ptr::null_mut();
+  check_status!(sys::napi_create_reference(env, array, 1, &mut reference), "Failed to create reference")?;
+
+  Ok(reference)
+}
+
+impl ResponseSerializer {
+  /// Compiles the 2xx response schema of a route, routes without one use the generic writer
+  #[cold]
+  pub fn from_schema(env: napi_env, schema: Option<&RouteSchema>) -> Result<Option<Self>> {
+    let source = match success_schema(schema) {
+      Some(res) => res,
+      None => return Ok(None),
+    };
+
+    let mut keys = vec![];
+    let plan = compile_plan(source, &mut keys);
+    if keys.is_empty() {
+      return Ok(None);
+    }
+
+    let reference = unsafe { create_keys_ref(env, &keys)? };
+
+    Ok(Some(Self {
+      plan,
+      keys: KeysRef(reference),
+      key_count: keys.len() as u32,
+    }))
+  }
+
+  #[inline]
+  unsafe fn load_keys(&self, env: napi_env) -> Result<Vec<napi_value>> {
+    let mut array = ptr::null_mut();
+    check_status!(sys::napi_get_reference_value(env, self.keys.0, &mut array), "Failed to get reference")?;
+
+    let mut keys = Vec::with_capacity(self.key_count as usize);
+    for index in 0..self.key_count {
+      let mut key = ptr::null_mut();
+      check_status!(sys::napi_get_element(env, array, index, &mut key), "Failed to get element")?;
+      keys.push(key);
+    }
+
+    Ok(keys)
+  }
+
+  /// Writes the value to JSON, this must be called on the JS thread
+  #[inline]
+  pub unsafe fn write(&self, env: napi_env, value: napi_value) -> Result<Bytes> {
+    let keys = self.load_keys(env)?;
+    let mut writer = JsonWriter::new(env);
+
+    write_planned(&mut writer, &keys, &self.plan, value, JsonKey::Root)?;
+
+    Ok(writer.finish())
+  }
+}
+
+#[inline(always)]
+unsafe fn is_plain_object(writer: &JsonWriter, value: napi_value, value_type: i32) -> Result<bool> {
+  if value_type != sys::ValueType::napi_object {
+    return Ok(false);
+  }
+
+  let mut is_array = false;
+  check_status!(sys::napi_is_array(writer.env, value, &mut is_array), "Failed to check array")?;
+
+  Ok(!is_array && !writer.is_uint8_array(value)?)
+}
+
+unsafe fn write_planned(
+  writer: &mut JsonWriter,
+  keys: &[napi_value],
+  plan: &Plan,
+  value: napi_value,
+  key: JsonKey,
+) -> Result<()> {
+  match plan {
+    Plan::Any => writer.write_any(value, key),
+    Plan::Object(fields) => write_planned_object(writer, keys, fields, value, key),
+    Plan::Array(items) => write_planned_array(writer, keys, items, value, key),
+  }
+}
+
+unsafe fn write_planned_object(
+  writer: &mut JsonWriter,
+  keys: &[napi_value],
+  fields: &[Field],
+  value: napi_value,
+  key: JsonKey,
+) -> Result<()> {
+  // Anything which doesn't match the schema such as null is written as it is
+  let value_type = writer.type_of(value)?;
+  if !is_plain_object(writer, value, value_type)? {
+    return writer.write_any(value, key);
+  }
+
+  writer.out.put_u8(b'{');
+  let mut first = true;
+
+  for field in fields {
+    let field_key = keys[field.key_index as usize];
+
+    let mut item = ptr::null_mut();
+    check_status!(sys::napi_get_property(writer.env, value, field_key, &mut item), "Failed to get property")?;
+
+    let (item, item_type) = match field.plan {
+      Plan::Any => writer.resolve(item, JsonKey::Name(field_key))?,
+      _ => (item, writer.type_of(item)?),
+    };
+
+    if JsonWriter::is_skipped(item_type) {
+      continue;
+    }
+
+    let prefix = if first { &field.prefix[1..] } else { &field.prefix[..] };
+    writer.out.put_slice(prefix);
+    first = false;
+
+    match &field.plan {
+      Plan::Any => writer.write_value(item, item_type)?,
+      plan => write_planned(writer, keys, plan, item, JsonKey::Name(field_key))?,
+    }
+  }
+
+  writer.out.put_u8(b'}');
+  Ok(())
+}
+
+unsafe fn write_planned_array(
+  writer: &mut JsonWriter,
+  keys: &[napi_value],
+  items: &Plan,
+  value: napi_value,
+  key: JsonKey,
+) -> Result<()> {
+  let mut is_array = false;
+  check_status!(sys::napi_is_array(writer.env, value, &mut is_array), "Failed to check array")?;
+
+  if !is_array {
+    return writer.write_any(value, key);
+  }
+
+  let mut length = 0;
+  check_status!(sys::napi_get_array_length(writer.env, value, &mut length), "Failed to get array length")?;
+
+  writer.out.put_u8(b'[');
+
+  for index in 0..length {
+    if index > 0 {
+      writer.out.put_u8(b',');
+    }
+
+    let mut item = ptr::null_mut();
+    check_status!(sys::napi_get_element(writer.env, value, index, &mut item), "Failed to get element")?;
+
+    write_planned(writer, keys, items, item, JsonKey::Index(index))?;
+  }
+
+  writer.out.put_u8(b']');
+  Ok(())
+}
@@ -4,12 +4,16 @@ use napi::bindgen_prelude::*;
 use lazy_static::lazy_static;
 use parking_lot::RwLock;
 
-use crate::{types::CallBackFunction, Methods};
+use crate::Methods;
 
-use super::read_only::{write_reader, ReadRoutes};
+use super::{
+  entry::RouteEntry,
+  read_only::{write_reader, ReadRoutes},
+  route_info::{RouteInfo, RouteOptions},
+};
 
-type ReaderLookup = Router<CallBackFunction>;
-type ThreadSafeLookup = RwLock<Router<CallBackFunction>>;
+type ReaderLookup = Router<RouteEntry>;
+type ThreadSafeLookup = RwLock<Router<RouteEntry>>;
 
 lazy_static! {
   static ref GLOBAL_DATA: InternalRoutes = InternalRoutes::new_manager();
@@ -26,6 +30,7 @@ struct InternalRoutes {
   put: ThreadSafeLookup,
   patch: ThreadSafeLookup,
   delete: ThreadSafeLookup,
+  registered: RwLock<Vec<RouteInfo>>,
 }
 
 impl InternalRoutes {
@@ -37,6 +42,7 @@ impl InternalRoutes {
       put: RwLock::new(Router::new()),
       patch: RwLock::new(Router::new()),
       delete: RwLock::new(Router::new()),
+      registered: RwLock::new(Vec::new()),
     }
   }
 
@@ -71,12 +77,34 @@ pub fn initialise_reader() {
 }
 
 #[cold]
-pub fn add_new_route(route: &str, method: Methods, function: CallBackFunction) -> Result<()> {
+pub fn add_new_route(
+  route: &str,
+  method: Methods,
+  entry: RouteEntry,
+  options: RouteOptions,
+) -> Result<()> {
   let lock = GLOBAL_DATA.get_rw_from_method(method);
   let mut writing = lock
     .write();
 
-  writing
-    .insert(route, function)
-    .map_err(|_| Error::new(Status::GenericFailure, "Error inserting route".to_string()))
+  writing.insert(route, entry).map_err(|e| {
+    Error::new(
+      Status::GenericFailure,
+      format!("Error inserting route {} {}: {}", method.as_str(), route, e),
+    )
+  })?;
+
+  GLOBAL_DATA.registered.write().push(RouteInfo {
+    method: method.as_str().to_string(),
+    path: route.to_string(),
+    name: options.name.clone(),
+    options,
+  });
+
+  Ok(())
+}
+
+#[cold]
+pub fn registered_routes() -> Vec<RouteInfo> {
+  GLOBAL_DATA.registered.read().clone()
 }
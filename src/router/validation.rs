@@ -0,0 +1,172 @@
+use actix_http::Request;
+use jsonschema::JSONSchema;
+use napi::Result;
+use serde_json::{Map, Value};
+
+use crate::request::{form::parse_query_string, helpers::make_js_error_string};
+
+use super::{read_only::get_params, route_info::RouteSchema};
+
+struct CompiledSchema {
+  source: Value,
+  compiled: JSONSchema,
+}
+
+impl CompiledSchema {
+  #[cold]
+  fn compile(source: &Value, location: &'static str) -> Result<Self> {
+    let compiled = JSONSchema::compile(source)
+      .map_err(|e| make_js_error_string(format!("Invalid {} schema: {}", location, e)))?;
+
+    Ok(Self { source: source.clone(), compiled })
+  }
+
+  /// Strings from the url or query are converted to the type the schema expects for that property
+  #[inline]
+  fn coerce_value(&self, name: &str, raw: Value) -> Value {
+    let property = self
+      .source
+      .get("properties")
+      .and_then(|properties| properties.get(name));
+
+    let kind = property.and_then(|property| property.get("type")).and_then(Value::as_str);
+    if kind != Some("array") {
+      return Self::coerce_scalar(kind, raw);
+    }
+
+    let item_kind = property
+      .and_then(|property| property.get("items"))
+      .and_then(|items| items.get("type"))
+      .and_then(Value::as_str);
+
+    let items = match raw {
+      Value::Array(items) => items,
+      other => vec![other],
+    };
+
+    Value::Array(items.into_iter().map(|item| Self::coerce_scalar(item_kind, item)).collect())
+  }
+
+  #[inline]
+  fn coerce_scalar(kind: Option<&str>, raw: Value) -> Value {
+    let raw = match raw {
+      Value::String(res) => res,
+      other => return other,
+    };
+
+    match kind {
+      Some("integer") => match raw.parse::<i64>() {
+        Ok(res) => Value::from(res),
+        Err(_) => Value::String(raw),
+      },
+      Some("number") => match raw.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+        Some(res) => Value::Number(res),
+        None => Value::String(raw),
+      },
+      Some("boolean") => match raw.as_str() {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(raw),
+      },
+      _ => Value::String(raw),
+    }
+  }
+
+  #[inline]
+  fn coerce_map(&self, values: impl Iterator<Item = (String, Value)>) -> Value {
+    let mut map = Map::new();
+
+    for (key, value) in values {
+      let coerced = self.coerce_value(&key, value);
+      map.insert(key, coerced);
+    }
+
+    Value::Object(map)
+  }
+
+  #[inline]
+  fn validate_into(&self, instance: &Value, location: &'static str, errors: &mut Vec<String>) {
+    if let Err(found) = self.compiled.validate(instance) {
+      for error in found {
+        errors.push(format!("{}{}: {}", location, error.instance_path, error));
+      }
+    }
+  }
+}
+
+/// The compiled JSON schemas for a route, these are checked on the worker thread
+/// before the request is handed over to JS
+pub struct RouteValidator {
+  params: Option<CompiledSchema>,
+  query: Option<CompiledSchema>,
+  body: Option<CompiledSchema>,
+}
+
+impl RouteValidator {
+  #[cold]
+  pub fn from_schema(schema: Option<&RouteSchema>) -> Result<Option<Self>> {
+    let schema = match schema {
+      Some(res) => res,
+      None => return Ok(None),
+    };
+
+    if schema.params.is_none() && schema.query.is_none() && schema.body.is_none() {
+      return Ok(None);
+    }
+
+    let compile = |source: &Option<Value>, location| match source {
+      Some(res) => CompiledSchema::compile(res, location).map(Some),
+      None => Ok(None),
+    };
+
+    Ok(Some(Self {
+      params: compile(&schema.params, "params")?,
+      query: compile(&schema.query, "query")?,
+      body: compile(&schema.body, "body")?,
+    }))
+  }
+
+  #[inline(always)]
+  pub fn needs_body(&self) -> bool {
+    self.body.is_some()
+  }
+
+  /// Checks the request against each schema, returning every error found
+  #[inline]
+  pub fn validate(&self, req: &Request, body: Option<&Value>) -> std::result::Result<(), Vec<String>> {
+    let mut errors = vec![];
+
+    if let Some(schema) = &self.params {
+      let params = get_params(req.path(), req.method().clone())
+        .map(|params| params.0)
+        .unwrap_or_default();
+
+      let instance = schema.coerce_map(params.into_iter().map(|(key, value)| (key, Value::String(value))));
+      schema.validate_into(&instance, "params", &mut errors);
+    }
+
+    if let Some(schema) = &self.query {
+      let query = req
+        .uri()
+        .query()
+        .map(|query| parse_query_string(query.as_bytes(), false))
+        .unwrap_or_default();
+
+      let instance = schema.coerce_map(query.into_iter());
+      schema.validate_into(&instance, "query", &mut errors);
+    }
+
+    if let Some(schema) = &self.body {
+      match body {
+        Some(instance) => schema.validate_into(instance, "body", &mut errors),
+        None => errors.push("body: a JSON body is required".to_string()),
+      }
+    }
+
+    if errors.is_empty() {
+      Ok(())
+    } else {
+      Err(errors)
+    }
+  }
+}
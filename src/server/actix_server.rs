@@ -1,28 +1,50 @@
 use std::{cell::UnsafeCell, convert::Infallible, rc::Rc};
 
-use actix_http::{HttpService, Request, Response};
+use actix_http::{body::BoxBody, error::DispatchError, HttpService, Protocol, Request, Response};
 use actix_server::Server;
-use actix_service::{Service, ServiceFactory};
-use bytes::Bytes;
-use futures::future::LocalBoxFuture;
+use actix_rt::net::TcpStream;
+use actix_service::{fn_service, Service, ServiceFactory, ServiceFactoryExt};
+use futures::future::{ready, LocalBoxFuture};
 use http::HeaderValue;
 use napi::sys;
 use tokio::sync::oneshot;
 
 use crate::{
     extras::scheduler::{pin_js_thread, try_pin_priority, reset_thread_affinity},
+    files::mounts::{find_mount, initialise_mounts},
     object_pool::{build_up_pool, get_stored_chunk, StoredPair},
-    router::{read_only::get_route, store::initialise_reader}, request::helpers::make_js_error,
+    router::{
+        openapi::build_openapi_document,
+        entry::RouteHandler,
+        read_only::get_route,
+        route_info::format_route_table,
+        store::{initialise_reader, registered_routes},
+    },
+    napi::json_writer::write_bigint_mode,
+    request::{
+        cookies::write_cookie_key,
+        forwarded::{write_trusted_proxies, ConnectionDestination},
+        helpers::{make_js_error, value_to_bytes, write_unchecked_headers},
+    },
+    response::compress::{compress_response, write_compression},
+    ws::route::WsUpgrade,
 };
 
 use super::{
     config::ServerConfig,
-    helpers::{get_failed_message, get_post_body}, shutdown::{attach_server_handle, try_own_start},
+    helpers::{
+        get_builtin_message, get_failed_message, get_post_body, get_validation_failed_message,
+        parse_json_body, BuiltinEndpoint,
+    },
+    multipart::{get_multipart_body, is_multipart_request},
+    proxy_protocol::{accept_proxied, ProxiedStream},
+    shutdown::{attach_server_handle, try_own_start},
 };
 
 struct ActixHttpServer {
     _hdr_srv: HeaderValue,
     object_pool: Rc<UnsafeCell<Vec<StoredPair>>>,
+    builtin: Vec<BuiltinEndpoint>,
 }
 
 impl ActixHttpServer {
@@ -46,7 +68,7 @@ impl ActixHttpServer {
 }
 
 impl Service<Request> for ActixHttpServer {
-    type Response = Response<Bytes>;
+    type Response = Response<BoxBody>;
     type Error = Infallible;
     type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
 
@@ -54,27 +76,77 @@ impl Service<Request> for ActixHttpServer {
 
     #[inline(always)]
     fn call(&self, mut req: Request) -> Self::Future {
+        for endpoint in &self.builtin {
+            if req.path() == endpoint.path {
+                let rsp = get_builtin_message(endpoint.body.clone(), endpoint.content_type.clone());
+                return Box::pin(ready(Ok(rsp.map_into_boxed_body())));
+            }
+        }
+
+        let result = match get_route(req.path(), req.method().clone()) {
+            Some(res) => res,
+            None => {
+                if let Some(mount) = find_mount(req.path(), req.method()) {
+                    return Box::pin(async move { Ok(mount.serve(&req).await) });
+                }
+
+                let rsp = get_failed_message().map(Response::map_into_boxed_body);
+                return Box::pin(ready(rsp));
+            }
+        };
+
+        if let RouteHandler::Static(prepared) = &result.handler {
+            return Box::pin(ready(Ok(prepared.to_response().map_into_boxed_body())));
+        }
+
         let vec_ref = self.object_pool.clone();
 
         Box::pin(async move {
-            let result = match get_route(req.path(), req.method().clone()) {
-                Some(res) => res,
-                None => {
-                    return get_failed_message();
-                }
-            };
+            let mut upgrade = None;
+
+            if let RouteHandler::Ws(route) = &result.handler {
+                upgrade = match WsUpgrade::new(route, &mut req) {
+                    Ok(res) => Some(res),
+                    Err(rsp) => return Ok(rsp),
+                };
+            }
 
             let mut body = None;
+            let mut multipart = None;
+            let mut temp_files = None;
+
+            let multipart_limits = result.multipart.as_ref().filter(|_| is_multipart_request(&req));
 
-            if req.method() == http::Method::POST {
-                body = match get_post_body(req.payload()).await {
+            if let Some(limits) = multipart_limits {
+                let (parts, files) = match get_multipart_body(&mut req, limits).await {
+                    Ok(res) => res,
+                    Err(rsp) => return Ok(rsp.map_into_boxed_body()),
+                };
+
+                multipart = Some(parts);
+                temp_files = Some(files);
+            } else if req.method() == http::Method::POST || result.reads_body() {
+                body = match get_post_body(&mut req).await {
                     Ok(body) => Some(body),
-                    Err(_) => {
-                        return get_failed_message();
-                    }
+                    Err(rsp) => return Ok(rsp.map_into_boxed_body()),
                 };
             }
 
+            let mut json_body = None;
+
+            if result.parse_json {
+                json_body = match parse_json_body(&req, body.as_ref()) {
+                    Ok(res) => res,
+                    Err(rsp) => return Ok(rsp.map_into_boxed_body()),
+                };
+            }
+
+            if let Some(validator) = &result.validator {
+                if let Err(errors) = validator.validate(&req, json_body.as_ref()) {
+                    return Ok(get_validation_failed_message(errors).map_into_boxed_body());
+                }
+            }
+
             let to_add_back = Self::get_mut_from_unsafe(&vec_ref);
             let mut js_obj = match to_add_back.pop() {
                 Some(res) => res,
@@ -82,18 +154,40 @@ impl Service<Request> for ActixHttpServer {
             };
 
             let (send, rec) = oneshot::channel();
-            js_obj.0 .0.store_self_data(req, send, body);
 
-            result.call(
-                js_obj.0 .1,
-                crate::napi::tsfn::ThreadsafeFunctionCallMode::NonBlocking,
-            );
+            js_obj.0 .0.store_self_data(req, send, body, json_body, multipart, result.serializer.as_deref());
+
+            match &result.handler {
+                RouteHandler::Js(callback) => {
+                    callback.call(
+                        js_obj.0 .1,
+                        crate::napi::tsfn::ThreadsafeFunctionCallMode::NonBlocking,
+                    );
+                }
+                RouteHandler::Sse(route) => route.call(&mut js_obj.0 .0),
+                RouteHandler::Ws(_) => {
+                    if let Some(upgrade) = &mut upgrade {
+                        upgrade.open(&mut js_obj.0 .0, js_obj.0 .1);
+                    }
+                }
+                // Answered before a request object is taken from the pool
+                RouteHandler::Static(_) => {}
+            }
+
+            let result = match (rec.await, upgrade) {
+                (Ok(res), Some(upgrade)) => Ok(upgrade.respond(res, js_obj.0 .0.get_data_val()).await),
+                (Ok(res), None) => {
+                    let req = js_obj.0 .0.get_data_val();
+                    let rsp = res.into_response(req).await;
 
-            let result = match rec.await {
-                Ok(res) => Ok(res.apply_to_response()),
-                Err(_) => get_failed_message(),
+                    Ok(if result.compress { compress_response(req, rsp) } else { rsp })
+                }
+                (Err(_), _) => get_failed_message().map(Response::map_into_boxed_body),
             };
 
+            // Uploads written to disk are only kept until the response is sent
+            drop(temp_files);
+
             // Saves a check check for length we can be sure that the vec is not full
             if to_add_back.len() == to_add_back.capacity() {
                 unsafe { std::hint::unreachable_unchecked() }
@@ -107,11 +201,14 @@ impl Service<Request> for ActixHttpServer {
 }
 
 #[derive(Clone)]
-struct AppFactory(usize);
+struct AppFactory {
+    pool_size: usize,
+    builtin: Vec<BuiltinEndpoint>,
+}
 
 impl ServiceFactory<Request> for AppFactory {
     type Config = ();
-    type Response = Response<Bytes>;
+    type Response = Response<BoxBody>;
     type Error = Infallible;
     type Service = ActixHttpServer;
     type InitError = ();
@@ -120,27 +217,87 @@ impl ServiceFactory<Request> for AppFactory {
     fn new_service(&self, _: ()) -> Self::Future {
         try_pin_priority();
 
-        let chunk_size = self.0;
+        let chunk_size = self.pool_size;
+        let builtin = self.builtin.clone();
 
         Box::pin(async move {
             Ok(ActixHttpServer {
                 _hdr_srv: HeaderValue::from_static("Walker"),
                 object_pool: Rc::new(UnsafeCell::new(get_stored_chunk(chunk_size))),
+                builtin,
             })
         })
     }
 }
 
+#[cold]
+fn build_builtin_endpoints(config: &ServerConfig) -> std::io::Result<Vec<BuiltinEndpoint>> {
+    let routes = registered_routes();
+    let mut endpoints = vec![];
+
+    if let Some(path) = &config.debug_routes_path {
+        endpoints.push(BuiltinEndpoint {
+            path: path.clone(),
+            body: format_route_table(&routes),
+            content_type: HeaderValue::from_static("text/plain; charset=UTF-8"),
+        });
+    }
+
+    if let Some(path) = &config.openapi_path {
+        let document = build_openapi_document(&routes, &config.openapi_title, &config.openapi_version);
+        let body = value_to_bytes(document)
+            .map_err(|_| std::io::Error::other("Error serialising OpenAPI document"))?;
+
+        endpoints.push(BuiltinEndpoint {
+            path: path.clone(),
+            body,
+            content_type: HeaderValue::from_static("application/json; charset=UTF-8"),
+        });
+    }
+
+    Ok(endpoints)
+}
+
 async fn create_sever(config: ServerConfig) -> std::io::Result<()> {
-    let pool_size = config.pool_per_worker_size;
+    let factory = AppFactory {
+        pool_size: config.pool_per_worker_size,
+        builtin: build_builtin_endpoints(&config)?,
+    };
+
+    let proxied_factory = factory.clone();
 
-    let srv = Server::build()
+    let mut builder = Server::build()
         .backlog(config.backlog as u32)
         .bind("walker_server_h1", &config.url, move || {
-            HttpService::build().finish(AppFactory(pool_size)).tcp()
-        })?
-        .workers(config.worker_threads)
-        .run();
+            HttpService::build()
+                .on_connect_ext(|io: &TcpStream, ext| {
+                    if let Ok(addr) = io.local_addr() {
+                        ext.insert(ConnectionDestination(addr));
+                    }
+                })
+                .finish(factory.clone())
+                .tcp()
+        })?;
+
+    if let Some(url) = &config.proxy_protocol_url {
+        builder = builder.bind("walker_server_proxy_protocol", url, move || {
+            let http = HttpService::build()
+                .on_connect_ext(|io: &ProxiedStream, ext| {
+                    if let Some(addr) = io.destination {
+                        ext.insert(ConnectionDestination(addr));
+                    }
+                })
+                .finish(proxied_factory.clone());
+
+            fn_service(|io: TcpStream| async move {
+                let (io, source) = accept_proxied(io).await.map_err(DispatchError::Io)?;
+                Ok((io, Protocol::Http1, source))
+            })
+            .and_then(http)
+        })?;
+    }
+
+    let srv = builder.workers(config.worker_threads).run();
 
     attach_server_handle(srv.handle());
 
@@ -162,6 +319,12 @@ pub fn start_server(config: ServerConfig, env: sys::napi_env) -> napi::Result<()
     
     reset_thread_affinity();
     initialise_reader();
+    initialise_mounts();
+    write_trusted_proxies(config.trusted_proxies.clone());
+    write_cookie_key(config.cookie_secret.as_deref());
+    write_unchecked_headers(config.unchecked_headers);
+    write_bigint_mode(config.bigint_json);
+    write_compression(config.compression.clone());
     unsafe { build_up_pool(env, config.get_pool_size())?; }
 
     // Lets set js priority here
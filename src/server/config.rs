@@ -1,9 +1,14 @@
-use std::cmp;
+use std::{cmp, net::IpAddr};
 
 use napi::Result;
 use halfbrown::HashMap;
+use ipnet::IpNet;
 
-use crate::request::helpers::{make_js_error, make_js_error_string};
+use crate::{
+    napi::json_writer::BigIntMode,
+    request::helpers::{make_js_error, make_js_error_string},
+    response::compress::CompressionSettings,
+};
 
 #[derive(Debug)]
 pub struct ServerConfig {
@@ -12,6 +17,16 @@ pub struct ServerConfig {
     pub pool_per_worker_size: usize,
     pub backlog: usize,
     pub debug: bool,
+    pub debug_routes_path: Option<String>,
+    pub openapi_path: Option<String>,
+    pub openapi_title: String,
+    pub openapi_version: String,
+    pub trusted_proxies: Vec<IpNet>,
+    pub proxy_protocol_url: Option<String>,
+    pub cookie_secret: Option<String>,
+    pub unchecked_headers: bool,
+    pub bigint_json: BigIntMode,
+    pub compression: CompressionSettings,
 }
 
 #[cold]
@@ -20,6 +35,21 @@ pub fn guess_optimal_worker_count() -> usize {
     cmp::max(4, count.saturating_sub(2))
 }
 
+/// Parses a comma separated list of CIDR ranges, plain addresses are treated as a single host
+#[cold]
+fn parse_trusted_proxies(list: &str) -> Result<Vec<IpNet>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .parse::<IpNet>()
+                .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| make_js_error_string(format!("Invalid trusted proxy provided: {}", entry)))
+        })
+        .collect()
+}
+
 
 impl ServerConfig {
     #[cold]
@@ -30,6 +60,16 @@ impl ServerConfig {
             pool_per_worker_size: 10_000,
             backlog: 1024,
             debug: false,
+            debug_routes_path: None,
+            openapi_path: None,
+            openapi_title: "Walker".to_string(),
+            openapi_version: "1.0.0".to_string(),
+            trusted_proxies: vec![],
+            proxy_protocol_url: None,
+            cookie_secret: None,
+            unchecked_headers: false,
+            bigint_json: BigIntMode::Error,
+            compression: CompressionSettings::default(),
         }
     }
 
@@ -60,12 +100,40 @@ impl ServerConfig {
             }
         };
         
+        let trusted_proxies = match config.get("trusted_proxies") {
+            Some(res) => parse_trusted_proxies(res)?,
+            None => vec![],
+        };
+
+        let cookie_secret = config.get("cookie_secret").cloned();
+        if cookie_secret.as_ref().is_some_and(|secret| secret.len() < 32) {
+            return Err(make_js_error("cookie_secret must be at least 32 bytes"));
+        }
+
+        let bigint_json = match config.get("bigint_json") {
+            Some(res) => BigIntMode::from_config(res)
+                .ok_or_else(|| make_js_error_string(format!("Invalid bigint_json provided: {}", res)))?,
+            None => BigIntMode::Error,
+        };
+
+        let compression = CompressionSettings::from_config(&config)?;
+
         Ok(Self {
             url,
             worker_threads: get_number_with_deault("worker_threads", guess_optimal_worker_count())?,
             pool_per_worker_size: get_number_with_deault("pool_per_worker_size", 10_000)?,
             backlog: get_number_with_deault("backlog", 1024)?,
             debug: get_bool_with_default("debug", false)?,
+            debug_routes_path: config.get("debug_routes_path").cloned(),
+            openapi_path: config.get("openapi_path").cloned(),
+            openapi_title: config.get("openapi_title").cloned().unwrap_or_else(|| "Walker".to_string()),
+            openapi_version: config.get("openapi_version").cloned().unwrap_or_else(|| "1.0.0".to_string()),
+            trusted_proxies,
+            proxy_protocol_url: config.get("proxy_protocol_url").cloned(),
+            cookie_secret,
+            unchecked_headers: get_bool_with_default("unchecked_headers", false)?,
+            bigint_json,
+            compression,
         })
     }
 
@@ -0,0 +1,118 @@
+use std::io::{self, Write};
+
+use actix_http::header::{HeaderMap, CONTENT_ENCODING};
+use brotli::DecompressorWriter;
+use bytes::{Bytes, BytesMut};
+use flate2::write::{GzDecoder, ZlibDecoder};
+
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+/// Collects the decoded body, writes past the limit fail so a small compressed
+/// body can't expand without bound before the size is checked
+pub struct LimitedWriter {
+    buffer: BytesMut,
+    limit: usize,
+    overflowed: bool,
+}
+
+impl Write for LimitedWriter {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buffer.len() + buf.len() > self.limit {
+            self.overflowed = true;
+            return Err(io::Error::other("Decoded body too large"));
+        }
+
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub enum DecodeError {
+    TooLarge,
+    Invalid,
+}
+
+/// Decodes a request body sent with a Content-Encoding as its chunks arrive
+pub enum BodyDecoder {
+    Gzip(GzDecoder<LimitedWriter>),
+    Deflate(ZlibDecoder<LimitedWriter>),
+    Brotli(Box<DecompressorWriter<LimitedWriter>>),
+}
+
+impl BodyDecoder {
+    /// Returns None for bodies which aren't encoded and the header value when the encoding isn't supported
+    pub fn from_headers(headers: &HeaderMap, limit: usize) -> Result<Option<Self>, String> {
+        let encoding = match headers.get(CONTENT_ENCODING) {
+            Some(res) => String::from_utf8_lossy(res.as_bytes()).trim().to_ascii_lowercase(),
+            None => return Ok(None),
+        };
+
+        let writer = LimitedWriter { buffer: BytesMut::with_capacity(1024), limit, overflowed: false };
+
+        let decoder = match encoding.as_str() {
+            "" | "identity" => return Ok(None),
+            "gzip" | "x-gzip" => Self::Gzip(GzDecoder::new(writer)),
+            "deflate" => Self::Deflate(ZlibDecoder::new(writer)),
+            "br" => Self::Brotli(Box::new(DecompressorWriter::new(writer, BROTLI_BUFFER_SIZE))),
+            _ => return Err(encoding),
+        };
+
+        Ok(Some(decoder))
+    }
+
+    #[inline]
+    fn writer(&self) -> &LimitedWriter {
+        match self {
+            Self::Gzip(decoder) => decoder.get_ref(),
+            Self::Deflate(decoder) => decoder.get_ref(),
+            Self::Brotli(decoder) => decoder.get_ref(),
+        }
+    }
+
+    #[cold]
+    fn error(&self) -> DecodeError {
+        if self.writer().overflowed {
+            DecodeError::TooLarge
+        } else {
+            DecodeError::Invalid
+        }
+    }
+
+    #[inline]
+    pub fn write(&mut self, chunk: &[u8]) -> Result<(), DecodeError> {
+        let result = match self {
+            Self::Gzip(decoder) => decoder.write_all(chunk),
+            Self::Deflate(decoder) => decoder.write_all(chunk),
+            Self::Brotli(decoder) => decoder.write_all(chunk),
+        };
+
+        result.map_err(|_| self.error())
+    }
+
+    /// Checks the compressed stream was complete and returns the decoded body
+    pub fn finish(mut self) -> Result<Bytes, DecodeError> {
+        let result = match &mut self {
+            Self::Gzip(decoder) => decoder.try_finish(),
+            Self::Deflate(decoder) => decoder.try_finish(),
+            Self::Brotli(decoder) => decoder.close(),
+        };
+
+        if result.is_err() {
+            return Err(self.error());
+        }
+
+        let writer = match self {
+            Self::Gzip(decoder) => decoder.finish().map_err(|_| DecodeError::Invalid)?,
+            Self::Deflate(decoder) => decoder.finish().map_err(|_| DecodeError::Invalid)?,
+            Self::Brotli(decoder) => decoder.into_inner().map_err(|_| DecodeError::Invalid)?,
+        };
+
+        Ok(writer.buffer.freeze())
+    }
+}
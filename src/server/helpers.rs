@@ -1,11 +1,24 @@
 use std::convert::Infallible;
 
-use actix_http::{Response, Payload};
+use actix_http::{header::CONTENT_TYPE, HttpMessage, Request, Response};
 use bytes::{Bytes, BytesMut};
 use futures::StreamExt;
+use serde_json::{json, Value};
+
+use crate::request::helpers::{is_json_content_type, value_to_bytes};
+
+use super::decompress::{BodyDecoder, DecodeError};
 
 const MAX_SIZE: usize = 262_144; // max payload size is 256k
 
+/// A response answered directly by the server without calling into JS
+#[derive(Clone)]
+pub struct BuiltinEndpoint {
+    pub path: String,
+    pub body: Bytes,
+    pub content_type: http::HeaderValue,
+}
+
 #[cold]
 #[inline(never)]
 pub fn get_failed_message() -> Result<Response<Bytes>, Infallible> {
@@ -17,19 +30,102 @@ pub fn get_failed_message() -> Result<Response<Bytes>, Infallible> {
 
 #[cold]
 #[inline(never)]
-pub async fn get_post_body(payload: &mut Payload) -> Result<Bytes, &'static str> {
+pub fn get_builtin_message(body: Bytes, content_type: http::HeaderValue) -> Response<Bytes> {
+    let mut rsp = Response::with_body(http::StatusCode::OK, body);
+    rsp.headers_mut().insert(CONTENT_TYPE, content_type);
+
+    rsp
+}
+
+#[cold]
+#[inline(never)]
+pub fn get_validation_failed_message(errors: Vec<String>) -> Response<Bytes> {
+    get_errors_message(http::StatusCode::BAD_REQUEST, errors)
+}
+
+#[cold]
+#[inline(never)]
+pub fn get_errors_message(status: http::StatusCode, errors: Vec<String>) -> Response<Bytes> {
+    let body = value_to_bytes(json!({ "errors": errors })).unwrap_or_default();
+
+    let mut rsp = Response::with_body(status, body);
+    rsp.headers_mut().insert(
+        CONTENT_TYPE,
+        http::HeaderValue::from_static("application/json; charset=UTF-8"),
+    );
+
+    rsp
+}
+
+/// Parses the request body as JSON, returning the response to send if it isn't valid JSON
+#[inline(always)]
+pub fn parse_json_body(req: &Request, body: Option<&Bytes>) -> Result<Option<Value>, Response<Bytes>> {
+    let body = match body {
+        Some(res) if !res.is_empty() => res,
+        _ => return Ok(None),
+    };
+
+    if !is_json_content_type(req.headers()) {
+        return Err(get_errors_message(
+            http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            vec!["body: expected an application/json content type".to_string()],
+        ));
+    }
+
+    match serde_json::from_slice(body) {
+        Ok(res) => Ok(Some(res)),
+        Err(e) => Err(get_validation_failed_message(vec![format!("body: invalid JSON, {}", e)])),
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn get_body_error(error: DecodeError) -> Response<Bytes> {
+    match error {
+        DecodeError::TooLarge => get_errors_message(
+            http::StatusCode::PAYLOAD_TOO_LARGE,
+            vec![format!("body: larger than {} bytes", MAX_SIZE)],
+        ),
+        DecodeError::Invalid => get_validation_failed_message(vec!["body: invalid compressed data".to_string()]),
+    }
+}
+
+/// Reads the whole body, bodies sent with a Content-Encoding are decoded as they arrive and
+/// the limit applies to the decoded size. Unsupported encodings are rejected with a 415
+#[cold]
+#[inline(never)]
+pub async fn get_post_body(req: &mut Request) -> Result<Bytes, Response<Bytes>> {
+    let mut decoder = match BodyDecoder::from_headers(req.headers(), MAX_SIZE) {
+        Ok(res) => res,
+        Err(encoding) => {
+            return Err(get_errors_message(
+                http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                vec![format!("body: unsupported content encoding {}", encoding)],
+            ))
+        }
+    };
+
+    let payload = req.payload();
     let mut body = BytesMut::with_capacity(1024);
+    let mut received = 0;
 
     while let Some(chunk) = payload.next().await {
-        let chunk = chunk.map_err(|_| "Error reading body")?;
+        let chunk = chunk.map_err(|_| get_validation_failed_message(vec!["body: error reading body".to_string()]))?;
 
         // limit max size of in-memory payload
-        if (body.len() + chunk.len()) > MAX_SIZE {
-            return Err("overflow");
+        received += chunk.len();
+        if received > MAX_SIZE {
+            return Err(get_body_error(DecodeError::TooLarge));
         }
 
-        body.extend_from_slice(&chunk);
+        match &mut decoder {
+            Some(decoder) => decoder.write(&chunk).map_err(get_body_error)?,
+            None => body.extend_from_slice(&chunk),
+        }
     }
 
-    Ok(body.freeze())
-}
\ No newline at end of file
+    match decoder {
+        Some(decoder) => decoder.finish().map_err(get_body_error),
+        None => Ok(body.freeze()),
+    }
+}
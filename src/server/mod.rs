@@ -1,5 +1,8 @@
 pub mod node_functions;
 mod config;
 mod actix_server;
+mod decompress;
 mod helpers;
+mod multipart;
+mod proxy_protocol;
 mod shutdown;
\ No newline at end of file
@@ -0,0 +1,218 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use actix_http::{header::CONTENT_TYPE, HttpMessage, Request, Response};
+use bytes::{Bytes, BytesMut};
+use futures::{channel::mpsc, SinkExt, StreamExt};
+use multer::{Constraints, Multipart, SizeLimit};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::AsyncWriteExt,
+};
+
+use crate::{
+    request::form::{MultipartPart, PartData},
+    router::entry::MultipartLimits,
+};
+
+use super::helpers::get_errors_message;
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Attempts at finding an unused temporary file name before giving up
+const TEMP_FILE_ATTEMPTS: usize = 16;
+
+type PayloadChunk = Result<Bytes, std::io::Error>;
+
+#[inline(always)]
+pub fn is_multipart_request(req: &Request) -> bool {
+    match req.headers().get(CONTENT_TYPE).and_then(|val| val.to_str().ok()) {
+        Some(res) => res.trim_start().to_ascii_lowercase().starts_with("multipart/form-data"),
+        None => false,
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn get_multipart_error(error: multer::Error) -> Response<Bytes> {
+    let status = match error {
+        multer::Error::FieldSizeExceeded { .. } | multer::Error::StreamSizeExceeded { .. } => {
+            http::StatusCode::PAYLOAD_TOO_LARGE
+        }
+        _ => http::StatusCode::BAD_REQUEST,
+    };
+
+    get_errors_message(status, vec![format!("body: {}", error)])
+}
+
+#[cold]
+#[inline(never)]
+fn get_part_too_large_error(name: &str, limit: u64) -> Response<Bytes> {
+    get_errors_message(
+        http::StatusCode::PAYLOAD_TOO_LARGE,
+        vec![format!("body: part \"{}\" is larger than {} bytes", name, limit)],
+    )
+}
+
+/// Deletes the temporary files written for a request when dropped, this happens once the response
+/// is sent or when the request is dropped because the client went away
+#[derive(Default)]
+pub struct TempFiles(Vec<PathBuf>);
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// A name which can't be guessed by other users of the temporary directory
+#[cold]
+fn next_temp_path(limits: &MultipartLimits) -> PathBuf {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_usize(TEMP_COUNTER.fetch_add(1, Ordering::Relaxed));
+
+    let name = format!("walker-upload-{}-{:016x}", std::process::id(), hasher.finish());
+
+    match &limits.temp_dir {
+        Some(dir) => PathBuf::from(dir).join(name),
+        None => std::env::temp_dir().join(name),
+    }
+}
+
+/// Creates a new file only the server user can read, an existing file or link is never opened
+#[cold]
+async fn create_temp_file(limits: &MultipartLimits) -> io::Result<(File, PathBuf)> {
+    for _ in 0..TEMP_FILE_ATTEMPTS {
+        let path = next_temp_path(limits);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        match options.open(&path).await {
+            Ok(file) => return Ok((file, path)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(io::ErrorKind::AlreadyExists.into())
+}
+
+#[inline]
+async fn read_parts(
+    chunks: mpsc::Receiver<PayloadChunk>,
+    boundary: String,
+    limits: &MultipartLimits,
+    created: &mut TempFiles,
+) -> Result<Vec<MultipartPart>, Response<Bytes>> {
+    // Part sizes are checked below since parts written to temporary files have their own limit
+    let constraints = Constraints::new().size_limit(SizeLimit::new().whole_stream(limits.max_body_size));
+
+    let mut multipart = Multipart::with_constraints(chunks, boundary, constraints);
+    let mut parts = vec![];
+
+    while let Some(mut field) = multipart.next_field().await.map_err(get_multipart_error)? {
+        let name = field.name().unwrap_or_default().to_string();
+        let filename = field.file_name().map(|name| name.to_string());
+        let content_type = field.content_type().map(|mime| mime.to_string());
+
+        let mut buffer = BytesMut::new();
+        let mut file: Option<(File, PathBuf, u64)> = None;
+
+        while let Some(chunk) = field.chunk().await.map_err(get_multipart_error)? {
+            if let Some((open, _, written)) = &mut file {
+                open.write_all(&chunk).await.map_err(|_| get_temp_file_error())?;
+                *written += chunk.len() as u64;
+
+                if *written > limits.max_file_size {
+                    return Err(get_part_too_large_error(&name, limits.max_file_size));
+                }
+                continue;
+            }
+
+            buffer.extend_from_slice(&chunk);
+
+            let should_spill = match limits.file_memory_limit {
+                Some(limit) => filename.is_some() && buffer.len() as u64 > limit,
+                None => false,
+            };
+
+            if should_spill {
+                let (mut open, path) = create_temp_file(limits).await.map_err(|_| get_temp_file_error())?;
+                created.0.push(path.clone());
+                open.write_all(&buffer).await.map_err(|_| get_temp_file_error())?;
+
+                file = Some((open, path, buffer.len() as u64));
+                buffer = BytesMut::new();
+            } else if buffer.len() as u64 > limits.max_part_size {
+                return Err(get_part_too_large_error(&name, limits.max_part_size));
+            }
+        }
+
+        let data = match file {
+            Some((mut open, path, written)) => {
+                open.flush().await.map_err(|_| get_temp_file_error())?;
+                PartData::File(path, written)
+            }
+            None => PartData::Memory(buffer.freeze()),
+        };
+
+        parts.push(MultipartPart { name, filename, content_type, data });
+    }
+
+    Ok(parts)
+}
+
+#[cold]
+#[inline(never)]
+fn get_temp_file_error() -> Response<Bytes> {
+    get_errors_message(
+        http::StatusCode::INTERNAL_SERVER_ERROR,
+        vec!["body: unable to write upload to a temporary file".to_string()],
+    )
+}
+
+/// Streams a multipart/form-data body into parts on the worker thread,
+/// large file parts are written to temporary files if the route allows it
+/// The files are deleted when the returned guard is dropped
+#[inline(never)]
+pub async fn get_multipart_body(
+    req: &mut Request,
+    limits: &MultipartLimits,
+) -> Result<(Vec<MultipartPart>, TempFiles), Response<Bytes>> {
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|val| val.to_str().ok())
+        .unwrap_or_default();
+
+    let boundary = multer::parse_boundary(content_type).map_err(get_multipart_error)?;
+
+    // The actix payload can't leave this thread, so we pump it through a channel for multer
+    let (mut sender, receiver) = mpsc::channel::<PayloadChunk>(4);
+    let payload = req.payload();
+
+    let pump = async move {
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()));
+            if sender.send(chunk).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    let mut created = TempFiles::default();
+    let (_, parts) = futures::join!(pump, read_parts(receiver, boundary, limits, &mut created));
+
+    Ok((parts?, created))
+}
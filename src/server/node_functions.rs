@@ -38,6 +38,31 @@ pub fn start_with_worker_count(env: Env, address: String, workers: u32) -> Resul
 /// pool_per_worker_size: The size of the pool per worker
 /// 
 /// debug: Whether to enable debug mode
+/// 
+/// debug_routes_path: A path which will respond with a table of all registered routes
+/// 
+/// openapi_path: A path which will respond with the OpenAPI document for the registered routes
+/// 
+/// openapi_title: The title used in the OpenAPI document
+/// 
+/// openapi_version: The version used in the OpenAPI document
+/// 
+/// trusted_proxies: A comma separated list of CIDR ranges, the forwarding headers are only read for requests from these
+/// 
+/// proxy_protocol_url: An extra url to listen on which expects each connection to start with a PROXY protocol v1 or v2 header
+/// 
+/// cookie_secret: A random secret of at least 32 bytes used to sign and encrypt cookies
+/// 
+/// unchecked_headers: Skip validating response header values, only use this if every header value is trusted
+/// 
+/// bigint_json: How BigInt values are sent as JSON, one of error (the default), string or number
+/// 
+/// compression: A comma separated list of encodings to compress responses with in order of preference, any of br, gzip and zstd
+/// 
+/// compression_min_size: Responses smaller than this many bytes are sent uncompressed, defaults to 1024
+/// 
+/// compression_types: A comma separated list of content types to compress, entries ending in a slash match every subtype.
+/// Defaults to text/, application/json, application/javascript, application/xml and image/svg+xml
 pub fn start_with_config(env: Env, config: HalfBrown<String, String>) -> Result<()> {
     let config = ServerConfig::from_config_blob(config.0)?;
 
@@ -0,0 +1,187 @@
+use std::{
+    io::{self, IoSlice},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::net::TcpStream;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V1_MAX_LENGTH: usize = 107;
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The addresses sent by the proxy, these are None for LOCAL and UNKNOWN connections
+/// in which case the addresses of the socket itself are used
+#[derive(Default)]
+struct ProxyHeader {
+    source: Option<SocketAddr>,
+    destination: Option<SocketAddr>,
+}
+
+/// A connection which has had its PROXY protocol header read,
+/// it carries the original destination the client connected to
+pub struct ProxiedStream {
+    inner: TcpStream,
+    pub destination: Option<SocketAddr>,
+}
+
+#[cold]
+#[inline(never)]
+fn invalid_header(reason: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+#[inline]
+fn parse_v1(line: &[u8]) -> io::Result<ProxyHeader> {
+    let line = std::str::from_utf8(line).map_err(|_| invalid_header("PROXY header is not valid text"))?;
+    let mut parts = line.trim_end_matches("\r\n").split(' ').skip(1);
+
+    let family = parts.next().unwrap_or_default();
+    if family == "UNKNOWN" {
+        return Ok(ProxyHeader::default());
+    }
+
+    if family != "TCP4" && family != "TCP6" {
+        return Err(invalid_header("PROXY header has an unknown protocol"));
+    }
+
+    let mut next_field = || parts.next().ok_or_else(|| invalid_header("PROXY header is missing fields"));
+
+    let source_ip: IpAddr = next_field()?.parse().map_err(|_| invalid_header("PROXY header has an invalid address"))?;
+    let destination_ip: IpAddr = next_field()?.parse().map_err(|_| invalid_header("PROXY header has an invalid address"))?;
+    let source_port: u16 = next_field()?.parse().map_err(|_| invalid_header("PROXY header has an invalid port"))?;
+    let destination_port: u16 = next_field()?.parse().map_err(|_| invalid_header("PROXY header has an invalid port"))?;
+
+    Ok(ProxyHeader {
+        source: Some(SocketAddr::new(source_ip, source_port)),
+        destination: Some(SocketAddr::new(destination_ip, destination_port)),
+    })
+}
+
+#[inline]
+fn parse_v2(command: u8, family: u8, addresses: &[u8]) -> io::Result<ProxyHeader> {
+    if command >> 4 != 2 {
+        return Err(invalid_header("PROXY header has an unsupported version"));
+    }
+
+    // LOCAL connections are health checks from the proxy itself
+    if command & 0x0F == 0 {
+        return Ok(ProxyHeader::default());
+    }
+
+    let port_at = |at: usize| u16::from_be_bytes([addresses[at], addresses[at + 1]]);
+
+    match family >> 4 {
+        1 if addresses.len() >= 12 => {
+            let source = Ipv4Addr::new(addresses[0], addresses[1], addresses[2], addresses[3]);
+            let destination = Ipv4Addr::new(addresses[4], addresses[5], addresses[6], addresses[7]);
+
+            Ok(ProxyHeader {
+                source: Some(SocketAddr::new(source.into(), port_at(8))),
+                destination: Some(SocketAddr::new(destination.into(), port_at(10))),
+            })
+        }
+        2 if addresses.len() >= 36 => {
+            let mut source = [0u8; 16];
+            let mut destination = [0u8; 16];
+            source.copy_from_slice(&addresses[..16]);
+            destination.copy_from_slice(&addresses[16..32]);
+
+            Ok(ProxyHeader {
+                source: Some(SocketAddr::new(Ipv6Addr::from(source).into(), port_at(32))),
+                destination: Some(SocketAddr::new(Ipv6Addr::from(destination).into(), port_at(34))),
+            })
+        }
+        1 | 2 => Err(invalid_header("PROXY header addresses are truncated")),
+        // Unix sockets and unspecified families carry no addresses we can use
+        _ => Ok(ProxyHeader::default()),
+    }
+}
+
+/// Reads just the PROXY header off the stream so the HTTP parser starts at the request,
+/// the v1 line is read a byte at a time so nothing past the header is consumed
+async fn read_header(io: &mut TcpStream) -> io::Result<ProxyHeader> {
+    let mut start = [0u8; 12];
+    io.read_exact(&mut start).await?;
+
+    if start == V2_SIGNATURE {
+        let mut rest = [0u8; 4];
+        io.read_exact(&mut rest).await?;
+
+        let length = u16::from_be_bytes([rest[2], rest[3]]) as usize;
+        let mut addresses = vec![0u8; length];
+        io.read_exact(&mut addresses).await?;
+
+        return parse_v2(rest[0], rest[1], &addresses);
+    }
+
+    if !start.starts_with(V1_PREFIX) {
+        return Err(invalid_header("Connection did not start with a PROXY header"));
+    }
+
+    let mut line = start.to_vec();
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LENGTH {
+            return Err(invalid_header("PROXY header is too long"));
+        }
+
+        line.push(io.read_u8().await?);
+    }
+
+    parse_v1(&line)
+}
+
+/// Accepts a connection from a proxy, returning the stream and the original client address
+#[inline(never)]
+pub async fn accept_proxied(mut io: TcpStream) -> io::Result<(ProxiedStream, Option<SocketAddr>)> {
+    let header = tokio::time::timeout(HEADER_TIMEOUT, read_header(&mut io))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "Timed out reading PROXY header"))??;
+
+    let source = header.source.or_else(|| io.peer_addr().ok());
+    let destination = header.destination.or_else(|| io.local_addr().ok());
+
+    Ok((ProxiedStream { inner: io, destination }, source))
+}
+
+impl AsyncRead for ProxiedStream {
+    #[inline(always)]
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ProxiedStream {
+    #[inline(always)]
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    #[inline(always)]
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    #[inline(always)]
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    #[inline(always)]
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    #[inline(always)]
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
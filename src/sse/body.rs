@@ -0,0 +1,61 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_http::body::{BodySize, MessageBody};
+use bytes::Bytes;
+use tokio::time::{interval_at, Instant, Interval, MissedTickBehavior};
+
+use crate::response::stream::StreamBody;
+
+use super::event::KEEP_ALIVE;
+
+/// The body of an event stream, a comment is sent whenever the connection has been quiet for the keep alive period
+pub struct SseBody {
+    inner: StreamBody,
+    keep_alive: Option<Interval>,
+}
+
+impl SseBody {
+    /// This creates a timer so it must be called on a worker thread
+    #[inline]
+    pub fn new(inner: StreamBody, keep_alive: Option<Duration>) -> Self {
+        let keep_alive = keep_alive.map(|period| {
+            let mut interval = interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            interval
+        });
+
+        Self { inner, keep_alive }
+    }
+}
+
+impl MessageBody for SseBody {
+    type Error = io::Error;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<io::Result<Bytes>>> {
+        let this = &mut *self;
+
+        if let Poll::Ready(chunk) = Pin::new(&mut this.inner).poll_next(cx) {
+            if let (Some(interval), Some(Ok(_))) = (&mut this.keep_alive, &chunk) {
+                interval.reset();
+            }
+
+            return Poll::Ready(chunk);
+        }
+
+        match this.keep_alive.as_mut().map(|interval| interval.poll_tick(cx)) {
+            Some(Poll::Ready(_)) => Poll::Ready(Some(Ok(KEEP_ALIVE.clone()))),
+            _ => Poll::Pending,
+        }
+    }
+}
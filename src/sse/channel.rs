@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+use napi::{Env, Result};
+use parking_lot::Mutex;
+
+use crate::response::stream::ChunkSender;
+
+use super::{
+    event::{encode_event, SseMessage},
+    stream::SseStream,
+};
+
+#[derive(Default)]
+struct ChannelState {
+    subscribers: Vec<ChunkSender>,
+    history: VecDeque<(String, Bytes)>,
+}
+
+#[napi]
+/// Sends each event to every subscribed stream, the event is encoded once
+/// and written to the connections without calling into JS for each one
+pub struct SseChannel {
+    state: Mutex<ChannelState>,
+    history_size: usize,
+    max_buffered: usize,
+}
+
+impl SseChannel {
+    #[cold]
+    pub fn new(history_size: usize, max_buffered: usize) -> Self {
+        Self { state: Mutex::new(ChannelState::default()), history_size, max_buffered }
+    }
+}
+
+#[napi]
+impl SseChannel {
+    #[napi]
+    /// Adds a stream to the channel, it is removed once the client disconnects or the stream is closed
+    /// If the browser sent a Last-Event-ID still in the history the events after it are sent first
+    pub fn subscribe(&self, stream: &SseStream) {
+        let sender = match stream.sender() {
+            Some(res) => res,
+            None => return,
+        };
+
+        let mut state = self.state.lock();
+
+        let missed = stream
+            .get_last_event_id()
+            .and_then(|last_id| state.history.iter().position(|(id, _)| id == last_id));
+
+        if let Some(position) = missed {
+            for (_, event) in state.history.iter().skip(position + 1) {
+                sender.send(event.clone());
+            }
+        }
+
+        state.subscribers.push(sender);
+    }
+
+    #[napi]
+    /// Sends an event to every subscribed stream, returns how many streams it was sent to
+    /// Streams which have fallen more than `maxBuffered` bytes behind are disconnected and removed
+    /// Throws if the id or event name contain a line break
+    pub fn send(&self, env: Env, message: SseMessage) -> Result<u32> {
+        let encoded = encode_event(env, message)?;
+        let mut state = self.state.lock();
+
+        if let (Some(id), true) = (encoded.id, self.history_size > 0) {
+            if state.history.len() == self.history_size {
+                state.history.pop_front();
+            }
+
+            state.history.push_back((id, encoded.bytes.clone()));
+        }
+
+        state.subscribers.retain(|subscriber| {
+            if subscriber.queued() > self.max_buffered {
+                subscriber.abort();
+                return false;
+            }
+
+            subscriber.send(encoded.bytes.clone())
+        });
+
+        Ok(state.subscribers.len() as u32)
+    }
+
+    #[napi]
+    /// The number of streams subscribed, closed streams are only removed when the next event is sent
+    pub fn subscriber_count(&self) -> u32 {
+        self.state.lock().subscribers.len() as u32
+    }
+}
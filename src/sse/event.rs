@@ -0,0 +1,129 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use napi::{bindgen_prelude::FromNapiValue, Env, JsUnknown, Result, ValueType};
+
+use crate::{
+    napi::{buff_str::BuffStr, json_writer::write_json},
+    request::helpers::{make_js_error, make_js_error_string},
+};
+
+/// Sent when nothing else has been sent for a while so proxies keep the connection open
+pub static KEEP_ALIVE: Bytes = Bytes::from_static(b":\n\n");
+
+/// A single server-sent event
+#[napi(object)]
+pub struct SseMessage {
+    /// The event name, the browser fires a `message` event when this is missing
+    pub event: Option<String>,
+    /// Strings are sent as they are, anything else is sent as JSON
+    #[napi(ts_type = "any")]
+    pub data: Option<JsUnknown>,
+    /// Sent back by the browser in the Last-Event-ID header when it reconnects
+    pub id: Option<String>,
+    /// How many milliseconds the browser waits before reconnecting
+    pub retry: Option<u32>,
+}
+
+/// An event ready to be written to any number of connections
+pub struct EncodedEvent {
+    pub id: Option<String>,
+    pub bytes: Bytes,
+}
+
+#[inline]
+fn check_field(name: &str, value: &str) -> Result<()> {
+    if value.contains(['\r', '\n', '\0']) {
+        return Err(make_js_error_string(format!("Invalid SSE {}: {}", name, value.escape_debug())));
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn write_field(buffer: &mut BytesMut, name: &[u8], value: &[u8]) {
+    buffer.put_slice(name);
+    buffer.put_slice(b": ");
+    buffer.put_slice(value);
+    buffer.put_u8(b'\n');
+}
+
+/// Each line of the data gets its own field, any of the three line endings split a line
+#[inline]
+fn write_data(buffer: &mut BytesMut, data: &[u8]) {
+    let mut start = 0;
+    let mut pos = 0;
+
+    while pos < data.len() {
+        match data[pos] {
+            b'\r' => {
+                write_field(buffer, b"data", &data[start..pos]);
+                if data.get(pos + 1) == Some(&b'\n') {
+                    pos += 1;
+                }
+                start = pos + 1;
+            }
+            b'\n' => {
+                write_field(buffer, b"data", &data[start..pos]);
+                start = pos + 1;
+            }
+            _ => {}
+        }
+
+        pos += 1;
+    }
+
+    write_field(buffer, b"data", &data[start..]);
+}
+
+#[inline]
+fn data_to_bytes(env: Env, data: JsUnknown) -> Result<Bytes> {
+    let value = data.0.value;
+
+    if data.get_type()? == ValueType::String {
+        return unsafe { BuffStr::from_napi_value(env.raw(), value) }.map(|res| res.0);
+    }
+
+    unsafe { write_json(env.raw(), value) }
+}
+
+/// Builds the `text/event-stream` framing for a message, this is done once
+/// no matter how many connections the message is sent to
+pub fn encode_event(env: Env, message: SseMessage) -> Result<EncodedEvent> {
+    let mut buffer = BytesMut::with_capacity(64);
+
+    if let Some(id) = &message.id {
+        check_field("id", id)?;
+        write_field(&mut buffer, b"id", id.as_bytes());
+    }
+
+    if let Some(event) = &message.event {
+        check_field("event", event)?;
+        write_field(&mut buffer, b"event", event.as_bytes());
+    }
+
+    if let Some(retry) = message.retry {
+        write_field(&mut buffer, b"retry", itoa::Buffer::new().format(retry).as_bytes());
+    }
+
+    if let Some(data) = message.data {
+        let data = data_to_bytes(env, data)?;
+        write_data(&mut buffer, &data);
+    }
+
+    if buffer.is_empty() {
+        return Err(make_js_error("An SSE message needs at least one field."));
+    }
+
+    buffer.put_u8(b'\n');
+
+    Ok(EncodedEvent { id: message.id, bytes: buffer.freeze() })
+}
+
+/// Tells the browser how long to wait before reconnecting
+#[inline]
+pub fn encode_retry(retry: u32) -> Bytes {
+    let mut buffer = BytesMut::with_capacity(16);
+    write_field(&mut buffer, b"retry", itoa::Buffer::new().format(retry).as_bytes());
+    buffer.put_u8(b'\n');
+
+    buffer.freeze()
+}
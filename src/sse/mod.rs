@@ -0,0 +1,6 @@
+pub mod body;
+pub mod channel;
+pub mod event;
+pub mod node_functions;
+pub mod request;
+pub mod stream;
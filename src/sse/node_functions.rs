@@ -0,0 +1,83 @@
+use std::{sync::Arc, time::Duration};
+
+use napi::{JsFunction, Result};
+
+use crate::{
+    napi::tsfn::ThreadsafeFunction,
+    router::{
+        entry::{RouteEntry, RouteHandler},
+        route_info::RouteOptions,
+        store::add_new_route,
+    },
+    Methods,
+};
+
+use super::{
+    channel::SseChannel,
+    stream::{call_sse_handler, SseRoute},
+};
+
+const DEFAULT_KEEP_ALIVE_MS: u32 = 15_000;
+const DEFAULT_MAX_BUFFERED: u32 = 1024 * 1024;
+
+/// Settings for an event stream route
+#[napi(object)]
+#[derive(Default)]
+pub struct SseOptions {
+    /// Milliseconds of quiet before a keep alive comment is sent, defaults to 15000 and 0 disables it
+    pub keep_alive: Option<u32>,
+    /// Sent to the browser when it connects to set how many milliseconds it waits before reconnecting
+    pub retry: Option<u32>,
+}
+
+/// Settings for a broadcast channel
+#[napi(object)]
+#[derive(Default)]
+pub struct SseChannelOptions {
+    /// How many of the latest events with an id are kept, a reconnecting browser is sent the events
+    /// after its Last-Event-ID when it subscribes again. Defaults to 0
+    pub history: Option<u32>,
+    /// How many bytes a subscriber can fall behind before it is disconnected, a client which stops
+    /// reading would otherwise keep every event in memory. Defaults to 1MB
+    pub max_buffered: Option<u32>,
+}
+
+#[cold]
+#[napi(ts_args_type = "route: string, callback: (stream: SseStream, request: SseRequest) => void, options?: SseOptions")]
+/// Adds a GET route which answers with a `text/event-stream`, the callback is given the open stream
+/// and the request. The stream is sent once the callback returns unless it sent another response,
+/// which can be used to turn away unauthorised clients. The request is a copy which stays readable while the stream is open
+pub fn sse(route: String, callback: JsFunction, options: Option<SseOptions>) -> Result<()> {
+    let options = options.unwrap_or_default();
+    let keep_alive = options.keep_alive.unwrap_or(DEFAULT_KEEP_ALIVE_MS);
+
+    let tsfn = ThreadsafeFunction::create_with_callback(callback.0.env, callback.0.value, 1024, call_sse_handler)?;
+    let sse_route = SseRoute {
+        callback: tsfn,
+        keep_alive: (keep_alive > 0).then(|| Duration::from_millis(keep_alive as u64)),
+        retry: options.retry,
+    };
+
+    let entry = RouteEntry {
+        handler: RouteHandler::Sse(Arc::new(sse_route)),
+        validator: None,
+        parse_json: false,
+        multipart: None,
+        serializer: None,
+        compress: false,
+    };
+
+    add_new_route(&route, Methods::GET, entry, RouteOptions::default())
+}
+
+#[cold]
+#[napi]
+/// Creates a channel which broadcasts events to every stream subscribed to it
+pub fn create_sse_channel(options: Option<SseChannelOptions>) -> SseChannel {
+    let options = options.unwrap_or_default();
+
+    SseChannel::new(
+        options.history.unwrap_or(0) as usize,
+        options.max_buffered.unwrap_or(DEFAULT_MAX_BUFFERED) as usize,
+    )
+}
@@ -0,0 +1,134 @@
+use actix_http::{header::HeaderMap, HttpMessage, Method, Request};
+use napi::{Env, JsUnknown, Result};
+
+use crate::{
+    napi::{buff_str::BuffStr, fast_str::FastStr, halfbrown::HalfBrown, json_value::JsonValue},
+    request::{
+        form::parse_query_string,
+        helpers::{convert_header_map, make_js_error},
+        RequestBlob,
+    },
+    router,
+};
+
+#[napi]
+/// The request which opened an event stream, passed to the handlers registered with `sse`.
+/// The request is copied so it can be read for as long as the stream is open, a response
+/// can only be sent in place of the stream before the handler returns
+pub struct SseRequest {
+    headers: HeaderMap,
+    method: Method,
+    path: String,
+    query: Option<String>,
+    blob: Option<*mut RequestBlob>,
+}
+
+impl SseRequest {
+    /// Copies the request on the worker thread before the handler is called
+    #[inline]
+    pub(crate) fn new(req: &Request) -> Self {
+        Self {
+            headers: req.headers().clone(),
+            method: req.method().clone(),
+            path: req.path().to_string(),
+            query: req.uri().query().map(String::from),
+            blob: None,
+        }
+    }
+
+    /// Lets the handler answer the request in place of the stream while it is running
+    #[inline]
+    pub(crate) fn attach(&mut self, blob: *mut RequestBlob) {
+        self.blob = Some(blob);
+    }
+
+    #[inline]
+    pub(crate) fn detach(&mut self) {
+        self.blob = None;
+    }
+
+    #[inline]
+    pub(crate) fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    #[inline]
+    fn blob(&mut self) -> Result<&mut RequestBlob> {
+        match self.blob {
+            Some(blob) => Ok(unsafe { &mut *blob }),
+            None => Err(make_js_error("Already sent response.")),
+        }
+    }
+}
+
+#[napi]
+impl SseRequest {
+    #[napi(ts_args_type = "name: string")]
+    /// Get the value of a header, this will be null if the header was not sent
+    pub fn get_header(&self, name: FastStr) -> Option<String> {
+        let header_val = self.headers.get(name.0)?;
+
+        Some(header_val.to_str().ok()?.to_string())
+    }
+
+    #[napi(ts_return_type = "Record<string, string>")]
+    /// Get every header as an object with each name and value
+    pub fn get_all_headers(&self) -> HalfBrown<String, String> {
+        convert_header_map(&self.headers)
+    }
+
+    #[napi]
+    /// Get the HTTP method of the request e.g. GET
+    pub fn get_method(&self) -> String {
+        self.method.as_str().to_string()
+    }
+
+    #[napi]
+    /// Get the path of the request without the query string
+    pub fn get_path(&self) -> String {
+        self.path.clone()
+    }
+
+    #[napi(ts_args_type = "nested?: boolean", ts_return_type = "Record<string, any> | null")]
+    /// Get the query parameters the same as `RequestBlob.getQueryParams`
+    /// this will be null if there is no query string
+    pub fn get_query_params(&self, nested: Option<bool>) -> Option<JsonValue> {
+        let query_string = self.query.as_ref()?;
+        let parsed = parse_query_string(query_string.as_bytes(), nested.unwrap_or(false));
+
+        Some(JsonValue(parsed.into()))
+    }
+
+    #[napi(ts_return_type = "Record<string, string> | null")]
+    /// Get the url parameters as an object with each key and value
+    /// this will only be null if an error has occurred
+    pub fn get_url_params(&self) -> Option<HalfBrown<String, String>> {
+        router::read_only::get_params(&self.path, self.method.clone())
+    }
+
+    #[napi]
+    /// Set the status code of a response sent instead of the stream
+    /// Throws once the handler has returned
+    pub fn set_status_code(&mut self, status: u16) -> Result<bool> {
+        Ok(self.blob()?.set_status_code(status))
+    }
+
+    #[napi(ts_args_type = "key: string, value: string")]
+    /// Set a header on a response sent instead of the stream
+    /// Throws once the handler has returned
+    pub fn set_header(&mut self, key: BuffStr, value: BuffStr) -> Result<()> {
+        self.blob()?.set_header(key, value)
+    }
+
+    #[napi(ts_args_type = "response: string")]
+    /// Sends text instead of the stream, throws once the handler has returned
+    pub fn send_text(&mut self, response: BuffStr) -> Result<()> {
+        self.blob()?.send_text(response)
+    }
+
+    #[napi(ts_args_type = "response: any")]
+    /// Sends an object as JSON instead of the stream, throws once the handler has returned
+    pub fn send_object(&mut self, env: Env, response: JsUnknown) -> Result<()> {
+        self.blob()?.send_object(env, response)
+    }
+}
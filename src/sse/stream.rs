@@ -0,0 +1,184 @@
+use std::{ffi::c_void, ptr, time::Duration};
+
+use napi::{
+    bindgen_prelude::{FromNapiMutRef, ToNapiValue},
+    sys::{self, napi_env, napi_value},
+    Env, JsObject, Result, Status,
+};
+
+use crate::{
+    napi::tsfn::ThreadsafeFunctionCallMode,
+    request::{helpers::make_js_error, RequestBlob},
+    response::{
+        stream::{response_stream, ChunkSender, ResponseStream},
+        InnerResp,
+    },
+    types::CallBackFunction,
+};
+
+use super::{
+    body::SseBody,
+    event::{encode_event, encode_retry, SseMessage},
+    request::SseRequest,
+};
+
+#[napi]
+/// An open event stream to a single client, passed to the handlers registered with `sse`
+pub struct SseStream {
+    stream: ResponseStream,
+    last_event_id: Option<String>,
+}
+
+impl SseStream {
+    #[inline]
+    pub(crate) fn sender(&self) -> Option<ChunkSender> {
+        self.stream.sender()
+    }
+
+    #[inline]
+    pub(crate) fn get_last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+}
+
+#[napi]
+impl SseStream {
+    #[napi(ts_return_type = "Promise<void>")]
+    /// Sends an event to the client, the promise resolves once there is room for more events
+    /// and rejects if the client has disconnected
+    /// Throws if the id or event name contain a line break
+    pub fn send(&self, env: Env, message: SseMessage) -> Result<JsObject> {
+        if self.stream.is_ended() {
+            return Err(make_js_error("The event stream is closed."));
+        }
+
+        let encoded = encode_event(env, message)?;
+        self.stream.push(encoded.bytes)?;
+
+        self.stream.drained_promise(env)
+    }
+
+    #[napi]
+    /// Ends the response, the browser will reconnect unless it is told not to
+    pub fn close(&mut self) {
+        self.stream.finish();
+    }
+
+    #[napi]
+    /// True once the client has disconnected or the stream has been closed
+    pub fn is_closed(&self) -> bool {
+        self.stream.is_ended()
+    }
+
+    #[napi]
+    /// The Last-Event-ID header sent by a reconnecting browser
+    pub fn last_event_id(&self) -> Option<String> {
+        self.last_event_id.clone()
+    }
+}
+
+/// A route registered with `sse`
+pub struct SseRoute {
+    pub callback: CallBackFunction,
+    pub keep_alive: Option<Duration>,
+    pub retry: Option<u32>,
+}
+
+/// Everything the JS thread needs to start an event stream
+struct SseCall {
+    blob: *mut RequestBlob,
+    request: SseRequest,
+    stream: SseStream,
+    body: SseBody,
+}
+
+impl SseRoute {
+    /// Opens the event stream and hands it to the JS handler along with a copy of the request,
+    /// the pooled request object is reused once the response is sent so it isn't given to JS.
+    /// The response is sent once the handler returns unless it sent something else
+    #[inline]
+    pub fn call(&self, blob: &mut RequestBlob) {
+        let request = SseRequest::new(blob.get_data_val());
+        let last_event_id = request
+            .headers()
+            .get("last-event-id")
+            .and_then(|val| val.to_str().ok())
+            .map(String::from);
+
+        let (stream, body) = response_stream();
+        if let Some(retry) = self.retry {
+            let _ = stream.push(encode_retry(retry));
+        }
+
+        let call = Box::new(SseCall {
+            blob,
+            request,
+            stream: SseStream { stream, last_event_id },
+            body: SseBody::new(body, self.keep_alive),
+        });
+
+        let call = Box::into_raw(call);
+        let status = self.callback.call_raw(call.cast(), ThreadsafeFunctionCallMode::NonBlocking);
+
+        if status != Status::Ok {
+            let call = unsafe { Box::from_raw(call) };
+            let _ = unsafe { &mut *call.blob }.send_result(InnerResp::ServerError);
+        }
+    }
+}
+
+/// The `call_js` callback for event stream handlers, the handler is called with the stream and the request
+pub(crate) unsafe extern "C" fn call_sse_handler(
+    raw_env: napi_env,
+    js_callback: napi_value,
+    _context: *mut c_void,
+    data: *mut c_void,
+) {
+    let SseCall { blob: blob_ptr, mut request, stream, body } = *Box::from_raw(data as *mut SseCall);
+
+    // env and/or callback can be null when shutting down
+    if raw_env.is_null() || js_callback.is_null() {
+        return;
+    }
+
+    let blob = &mut *blob_ptr;
+    request.attach(blob_ptr);
+
+    let request = match SseRequest::to_napi_value(raw_env, request) {
+        Ok(res) => res,
+        Err(_) => {
+            let _ = blob.send_result(InnerResp::ServerError);
+            return;
+        }
+    };
+
+    let stream = match SseStream::to_napi_value(raw_env, stream) {
+        Ok(res) => res,
+        Err(_) => {
+            let _ = blob.send_result(InnerResp::ServerError);
+            return;
+        }
+    };
+
+    let mut recv = ptr::null_mut();
+    sys::napi_get_undefined(raw_env, &mut recv);
+
+    let args = [stream, request];
+    let status = sys::napi_call_function(raw_env, recv, js_callback, 2, args.as_ptr(), ptr::null_mut());
+
+    // The blob goes back to the pool with the response, the handler can keep the request after an await
+    if let Ok(request) = SseRequest::from_napi_mut_ref(raw_env, request) {
+        request.detach();
+    }
+
+    if blob.sent {
+        return;
+    }
+
+    let response = match status {
+        sys::Status::napi_ok => InnerResp::Sse(body),
+        _ => InnerResp::ServerError,
+    };
+
+    let _ = blob.send_result(response);
+}
@@ -0,0 +1,255 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+use actix_codec::Encoder;
+use actix_http::ws::{CloseCode, CloseReason, Codec, Message};
+use bytes::{Bytes, BytesMut};
+use napi::{
+    bindgen_prelude::FromNapiValue,
+    check_status,
+    sys::{self, napi_env, napi_ref, napi_value},
+    Result, ValueType,
+};
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+use crate::{
+    napi::{buff_str::BuffStr, bytes_recv::JsBytes},
+    request::helpers::make_js_error_string,
+    response::stream::ChunkSender,
+};
+
+use super::rooms::{join_room, leave_all_rooms, leave_room};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Close reasons have to fit in a control frame along with the code
+const MAX_REASON_LEN: usize = 123;
+
+/// A message sent from JS, strings are sent as text frames and arrays as binary frames
+pub enum WsData {
+    Text(Bytes),
+    Binary(Bytes),
+}
+
+impl FromNapiValue for WsData {
+    #[inline(always)]
+    unsafe fn from_napi_value(env: napi_env, napi_val: napi_value) -> Result<Self> {
+        let mut value_type = 0;
+        check_status!(sys::napi_typeof(env, napi_val, &mut value_type), "Failed to get the type of the message")?;
+
+        if ValueType::from(value_type) == ValueType::String {
+            return Ok(Self::Text(BuffStr::from_napi_value(env, napi_val)?.0));
+        }
+
+        Ok(Self::Binary(JsBytes::from_napi_value(env, napi_val)?.0))
+    }
+}
+
+impl WsData {
+    /// Server frames are never masked so the same bytes can be sent to every connection
+    #[inline]
+    pub fn encode(self) -> Bytes {
+        let message = match self {
+            WsData::Text(text) => match text.try_into() {
+                Ok(text) => Message::Text(text),
+                Err(_) => return Bytes::new(),
+            },
+            WsData::Binary(data) => Message::Binary(data),
+        };
+
+        encode_frame(message)
+    }
+}
+
+#[inline]
+pub fn encode_frame(message: Message) -> Bytes {
+    let mut buffer = BytesMut::new();
+    let _ = Codec::new().encode(message, &mut buffer);
+
+    buffer.freeze()
+}
+
+/// Codes which can't be sent in a close frame
+#[inline]
+fn is_valid_close_code(code: u16) -> bool {
+    matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999)
+}
+
+/// A reference to the JS object for a connection, this is only touched on the JS thread
+pub struct JsRef(pub napi_ref);
+
+unsafe impl Send for JsRef {}
+
+/// The state of a socket shared by the worker reading frames and the JS handle
+pub struct WsState {
+    pub id: u64,
+    sender: ChunkSender,
+    /// Sockets with more than this many bytes waiting to be written are dropped
+    max_buffered: usize,
+    closed: AtomicBool,
+    /// The code and reason the server closed the connection with
+    server_close: Mutex<Option<(u16, String)>>,
+    pub shutdown: Notify,
+    pub rooms: Mutex<Vec<String>>,
+    pub js_ref: Mutex<Option<JsRef>>,
+}
+
+impl WsState {
+    #[inline]
+    pub fn new(sender: ChunkSender, max_buffered: usize) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            sender,
+            max_buffered,
+            closed: AtomicBool::new(false),
+            server_close: Mutex::new(None),
+            shutdown: Notify::new(),
+            rooms: Mutex::new(Vec::new()),
+            js_ref: Mutex::new(None),
+        }
+    }
+
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Queues an encoded frame, returns false once the socket is closed
+    /// A client which has stopped reading is dropped instead of queueing more frames for it
+    #[inline]
+    pub fn send_frame(&self, frame: Bytes) -> bool {
+        if self.is_closed() || frame.is_empty() {
+            return false;
+        }
+
+        if self.sender.queued() > self.max_buffered {
+            self.overflow();
+            return false;
+        }
+
+        self.sender.send(frame)
+    }
+
+    /// Drops the connection with the frames still queued, a close frame would only wait behind them.
+    /// This can be called while a broadcast holds the rooms so the reader leaves them once it stops
+    #[cold]
+    fn overflow(&self) {
+        if self.closed.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        *self.server_close.lock() = Some((1013, String::from("Send buffer full")));
+
+        self.sender.abort();
+        self.shutdown.notify_one();
+    }
+
+    /// Sends a close frame and ends the connection, returns false if it was already closed
+    pub fn close(&self, reason: Option<CloseReason>) -> bool {
+        if self.closed.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+
+        let code = reason.as_ref().map_or(1005, |reason| u16::from(reason.code));
+        let description = reason.as_ref().and_then(|reason| reason.description.clone()).unwrap_or_default();
+        *self.server_close.lock() = Some((code, description));
+
+        self.sender.send(encode_frame(Message::Close(reason)));
+        self.end();
+
+        true
+    }
+
+    /// Ends the connection without a close frame
+    #[inline]
+    pub fn terminate(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.end();
+    }
+
+    #[inline]
+    fn end(&self) {
+        self.sender.finish();
+        self.shutdown.notify_one();
+        leave_all_rooms(self);
+    }
+
+    /// The code and reason sent when the server closed the connection
+    #[inline]
+    pub fn take_server_close(&self) -> Option<(u16, String)> {
+        self.server_close.lock().take()
+    }
+}
+
+#[napi]
+/// An open WebSocket, passed to the handlers registered with `ws`
+pub struct WsConnection {
+    pub(crate) state: Arc<WsState>,
+}
+
+#[napi]
+impl WsConnection {
+    #[napi(ts_args_type = "message: string | Uint8Array")]
+    /// Sends a text frame for strings and a binary frame for arrays
+    /// Returns false if the socket has already closed
+    pub fn send(&self, message: WsData) -> bool {
+        self.state.send_frame(message.encode())
+    }
+
+    #[napi]
+    /// Closes the connection with a code and reason, the code defaults to 1000
+    /// Throws if the code can't be sent in a close frame or the reason is longer than 123 bytes
+    pub fn close(&self, code: Option<u16>, reason: Option<String>) -> napi::Result<()> {
+        let code = code.unwrap_or(1000);
+        if !is_valid_close_code(code) {
+            return Err(make_js_error_string(format!("Invalid close code: {}", code)));
+        }
+
+        if reason.as_ref().is_some_and(|reason| reason.len() > MAX_REASON_LEN) {
+            return Err(make_js_error_string(format!("Close reason longer than {} bytes", MAX_REASON_LEN)));
+        }
+
+        self.state.close(Some(CloseReason { code: CloseCode::from(code), description: reason }));
+        Ok(())
+    }
+
+    #[napi]
+    /// True once either side has closed the connection
+    pub fn is_closed(&self) -> bool {
+        self.state.is_closed()
+    }
+
+    #[napi]
+    /// The number of bytes waiting to be written to the connection
+    pub fn buffered_amount(&self) -> u32 {
+        self.state.sender.queued() as u32
+    }
+
+    #[napi]
+    /// A number unique to this connection
+    pub fn id(&self) -> i64 {
+        self.state.id as i64
+    }
+
+    #[napi]
+    /// Adds the connection to a room so it receives messages sent with `wsBroadcast`
+    /// Connections leave every room when they close
+    pub fn join(&self, room: String) {
+        join_room(&self.state, room);
+    }
+
+    #[napi]
+    /// Removes the connection from a room
+    pub fn leave(&self, room: String) {
+        leave_room(&self.state, &room);
+    }
+
+    #[napi]
+    /// The rooms this connection has joined
+    pub fn rooms(&self) -> Vec<String> {
+        self.state.rooms.lock().clone()
+    }
+}
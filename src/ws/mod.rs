@@ -0,0 +1,4 @@
+pub mod connection;
+pub mod node_functions;
+pub mod rooms;
+pub mod route;
@@ -0,0 +1,102 @@
+use std::{ptr, sync::Arc, time::Duration};
+
+use napi::{sys, Env, JsObject, JsUnknown, Result, ValueType};
+
+use crate::{
+    napi::tsfn::ThreadsafeFunction,
+    request::helpers::make_js_error_string,
+    router::{
+        entry::{RouteEntry, RouteHandler},
+        route_info::RouteOptions,
+        store::add_new_route,
+    },
+    Methods,
+};
+
+use super::{
+    connection::WsData,
+    rooms::{broadcast, room_size},
+    route::{call_ws_handler, FnRef, WsRoute, MAX_QUEUED_EVENTS},
+};
+
+const DEFAULT_MAX_MESSAGE_SIZE: u32 = 1_048_576; // 1mb per message
+const DEFAULT_PING_INTERVAL_MS: u32 = 30_000;
+const DEFAULT_MAX_BUFFERED_AMOUNT: u32 = 1_048_576;
+
+/// Settings for a WebSocket route
+#[napi(object)]
+#[derive(Default)]
+pub struct WsOptions {
+    /// The largest message in bytes a client can send, larger messages close the socket with 1009. Defaults to 1MB
+    pub max_message_size: Option<u32>,
+    /// Milliseconds between pings, a client which hasn't answered the last ping is disconnected.
+    /// Defaults to 30000 and 0 disables pings
+    pub ping_interval: Option<u32>,
+    /// How many bytes can wait to be written to a socket, a socket over it is disconnected and
+    /// `onClose` is called with 1013. Defaults to 1MB
+    pub max_buffered_amount: Option<u32>,
+}
+
+#[cold]
+fn handler_ref(env: Env, handlers: &JsObject, name: &str) -> Result<Option<FnRef>> {
+    let handler = handlers.get_named_property::<JsUnknown>(name)?;
+
+    match handler.get_type()? {
+        ValueType::Undefined | ValueType::Null => return Ok(None),
+        ValueType::Function => {}
+        _ => return Err(make_js_error_string(format!("{} must be a function", name))),
+    }
+
+    let value = handler.0.value;
+    let mut reference = ptr::null_mut();
+    napi::check_status!(unsafe { sys::napi_create_reference(env.raw(), value, 1, &mut reference) })?;
+
+    Ok(Some(FnRef(reference)))
+}
+
+#[cold]
+#[napi(ts_args_type = "route: string, handlers: WsHandlers, options?: WsOptions")]
+/// Adds a route which upgrades GET requests to a WebSocket. `onOpen` is called with the socket and
+/// the request, sending a response from it refuses the upgrade. `onMessage` is called with strings for
+/// text frames and Buffers for binary frames, then `onClose` is called with the close code and reason
+/// Pings from the client are answered without calling into JS, sockets stop being read while the
+/// handlers are more than 1024 events behind
+pub fn ws(env: Env, route: String, handlers: JsObject, options: Option<WsOptions>) -> Result<()> {
+    let options = options.unwrap_or_default();
+    let ping_interval = options.ping_interval.unwrap_or(DEFAULT_PING_INTERVAL_MS);
+
+    let ws_route = WsRoute {
+        tsfn: ThreadsafeFunction::create_with_callback(env.raw(), ptr::null_mut(), MAX_QUEUED_EVENTS, call_ws_handler)?,
+        on_open: handler_ref(env, &handlers, "onOpen")?,
+        on_message: handler_ref(env, &handlers, "onMessage")?,
+        on_close: handler_ref(env, &handlers, "onClose")?,
+        max_message_size: options.max_message_size.unwrap_or(DEFAULT_MAX_MESSAGE_SIZE) as usize,
+        max_buffered: options.max_buffered_amount.unwrap_or(DEFAULT_MAX_BUFFERED_AMOUNT) as usize,
+        ping_interval: (ping_interval > 0).then(|| Duration::from_millis(ping_interval as u64)),
+    };
+
+    let entry = RouteEntry {
+        handler: RouteHandler::Ws(Arc::new(ws_route)),
+        validator: None,
+        parse_json: false,
+        multipart: None,
+        serializer: None,
+        compress: false,
+    };
+
+    add_new_route(&route, Methods::GET, entry, RouteOptions::default())
+}
+
+#[napi(ts_args_type = "room: string, message: string | Uint8Array, exceptId?: number")]
+/// Sends a message to every socket in a room, the frame is built once and written to each
+/// socket on the worker threads. Pass a socket id to leave out the sender
+/// Returns how many sockets the message was sent to
+pub fn ws_broadcast(room: String, message: WsData, except_id: Option<i64>) -> u32 {
+    broadcast(&room, message.encode(), except_id.map(|id| id as u64))
+}
+
+#[napi]
+/// The number of sockets in a room
+pub fn ws_room_size(room: String) -> u32 {
+    room_size(&room)
+}
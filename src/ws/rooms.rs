@@ -0,0 +1,77 @@
+use std::{collections::HashMap, sync::Arc};
+
+use bytes::Bytes;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+
+use super::connection::WsState;
+
+lazy_static! {
+    static ref ROOMS: RwLock<HashMap<String, Vec<Arc<WsState>>>> = RwLock::new(HashMap::new());
+}
+
+/// Closing takes the socket's room list to leave every room, so checking for a close while holding
+/// it means a socket which closed after `join` was called is never added back
+pub fn join_room(state: &Arc<WsState>, room: String) {
+    let mut joined = state.rooms.lock();
+    if state.is_closed() || joined.contains(&room) {
+        return;
+    }
+
+    ROOMS.write().entry(room.clone()).or_default().push(state.clone());
+    joined.push(room);
+}
+
+#[inline]
+fn remove_member(rooms: &mut HashMap<String, Vec<Arc<WsState>>>, room: &str, id: u64) {
+    if let Some(members) = rooms.get_mut(room) {
+        members.retain(|member| member.id != id);
+
+        if members.is_empty() {
+            rooms.remove(room);
+        }
+    }
+}
+
+pub fn leave_room(state: &WsState, room: &str) {
+    let mut joined = state.rooms.lock();
+    let position = match joined.iter().position(|joined| joined == room) {
+        Some(res) => res,
+        None => return,
+    };
+
+    joined.swap_remove(position);
+    remove_member(&mut ROOMS.write(), room, state.id);
+}
+
+pub fn leave_all_rooms(state: &WsState) {
+    let joined = std::mem::take(&mut *state.rooms.lock());
+    if joined.is_empty() {
+        return;
+    }
+
+    let mut rooms = ROOMS.write();
+    for room in &joined {
+        remove_member(&mut rooms, room, state.id);
+    }
+}
+
+/// Sends an encoded frame to every connection in a room, returns how many it was sent to
+pub fn broadcast(room: &str, frame: Bytes, except: Option<u64>) -> u32 {
+    let rooms = ROOMS.read();
+    let members = match rooms.get(room) {
+        Some(res) => res,
+        None => return 0,
+    };
+
+    members
+        .iter()
+        .filter(|member| Some(member.id) != except)
+        .filter(|member| member.send_frame(frame.clone()))
+        .count() as u32
+}
+
+#[inline]
+pub fn room_size(room: &str) -> u32 {
+    ROOMS.read().get(room).map_or(0, |members| members.len() as u32)
+}
@@ -0,0 +1,465 @@
+use std::{ffi::c_void, ptr, sync::Arc, time::Duration};
+
+use actix_codec::Decoder;
+use actix_http::{
+    body::BoxBody,
+    ws::{handshake, CloseCode, CloseReason, Codec, Frame, Item, Message, ProtocolError},
+    Payload, Request, Response, ResponseBuilder,
+};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use napi::{
+    bindgen_prelude::ToNapiValue,
+    sys::{self, napi_env, napi_ref, napi_value},
+    Status,
+};
+use tokio::time::{interval_at, Instant, Interval, MissedTickBehavior};
+
+use crate::{
+    napi::tsfn::{ThreadsafeFunctionCallMode, ThreadsafeFunction},
+    request::RequestBlob,
+    response::{
+        override_headers,
+        stream::{chunk_channel, StreamBody},
+        InnerResp, JsResponse,
+    },
+};
+
+use super::{
+    connection::{encode_frame, JsRef, WsConnection, WsState},
+    rooms::leave_all_rooms,
+};
+
+/// How many events for a route can wait for the JS thread, sockets stop being read once it is full
+pub const MAX_QUEUED_EVENTS: usize = 1024;
+
+const QUEUE_FULL_RETRY: Duration = Duration::from_millis(1);
+
+/// A reference to a handler function, these live as long as the route
+pub struct FnRef(pub napi_ref);
+
+unsafe impl Send for FnRef {}
+unsafe impl Sync for FnRef {}
+
+/// A route registered with `ws`, every event for its sockets is sent through one
+/// threadsafe function so the handlers are called in the order things happened
+pub struct WsRoute {
+    pub tsfn: ThreadsafeFunction,
+    pub on_open: Option<FnRef>,
+    pub on_message: Option<FnRef>,
+    pub on_close: Option<FnRef>,
+    pub max_message_size: usize,
+    pub max_buffered: usize,
+    pub ping_interval: Option<Duration>,
+}
+
+enum WsEvent {
+    Open {
+        blob: *mut RequestBlob,
+        blob_ref: napi_ref,
+        state: Arc<WsState>,
+        body: StreamBody,
+    },
+    Text(Arc<WsState>, Bytes),
+    Binary(Arc<WsState>, Bytes),
+    Close(Arc<WsState>, u16, String),
+}
+
+struct WsCall {
+    route: &'static WsRoute,
+    event: WsEvent,
+}
+
+impl WsRoute {
+    /// Gives the event back with the status if it couldn't be queued for the JS thread
+    #[inline]
+    fn dispatch(&'static self, event: WsEvent) -> Result<(), (Status, WsEvent)> {
+        let call = Box::into_raw(Box::new(WsCall { route: self, event }));
+        let status = self.tsfn.call_raw(call.cast(), ThreadsafeFunctionCallMode::NonBlocking);
+
+        if status == Status::Ok {
+            return Ok(());
+        }
+
+        let call = unsafe { Box::from_raw(call) };
+        Err((status, call.event))
+    }
+
+    /// Waits for room in the queue, the socket isn't read in the meantime so a client sending
+    /// faster than the handlers run is slowed down. Returns false if the route is shutting down
+    async fn dispatch_waiting(&'static self, mut event: WsEvent) -> bool {
+        loop {
+            match self.dispatch(event) {
+                Ok(()) => return true,
+                Err((Status::QueueFull, returned)) => {
+                    event = returned;
+                    tokio::time::sleep(QUEUE_FULL_RETRY).await;
+                }
+                Err(_) => return false,
+            }
+        }
+    }
+}
+
+/// A request which is being upgraded to a WebSocket, the handshake is only
+/// answered once the open handler has accepted it
+pub struct WsUpgrade {
+    route: &'static WsRoute,
+    handshake: ResponseBuilder,
+    payload: Payload,
+    state: Arc<WsState>,
+    body: Option<StreamBody>,
+}
+
+impl WsUpgrade {
+    /// Checks the handshake headers, invalid upgrades are answered straight away
+    #[inline]
+    pub fn new(route: &'static WsRoute, req: &mut Request) -> Result<Self, Response<BoxBody>> {
+        let handshake = handshake(req.head()).map_err(Response::from)?;
+
+        let (sender, body) = chunk_channel();
+
+        Ok(Self {
+            route,
+            handshake,
+            payload: req.take_payload(),
+            state: Arc::new(WsState::new(sender, route.max_buffered)),
+            body: Some(body),
+        })
+    }
+
+    /// Calls the open handler with the socket and the request
+    #[inline]
+    pub fn open(&mut self, blob: &mut RequestBlob, blob_ref: napi_ref) {
+        let body = match self.body.take() {
+            Some(res) => res,
+            None => return,
+        };
+
+        let event = WsEvent::Open { blob, blob_ref, state: self.state.clone(), body };
+        if self.route.dispatch(event).is_err() {
+            let _ = blob.send_result(InnerResp::ServerError);
+        }
+    }
+
+    /// Switches protocols if the open handler accepted the socket, otherwise sends the handler's response
+    pub async fn respond(self, res: JsResponse, req: &Request) -> Response<BoxBody> {
+        let JsResponse { inner, status_code, headers } = res;
+
+        let body = match inner {
+            InnerResp::WebSocket(body) => body,
+            inner => {
+                self.state.terminate();
+                return JsResponse { inner, status_code, headers }.into_response(req).await;
+            }
+        };
+
+        let WsUpgrade { route, mut handshake, payload, state, .. } = self;
+        let mut rsp = handshake.message_body(body).map_or_else(Response::from, Response::map_into_boxed_body);
+        override_headers(rsp.headers_mut(), headers);
+
+        actix_rt::spawn(read_frames(route, state, payload));
+
+        rsp
+    }
+}
+
+/// A message split over several frames
+struct Fragmented {
+    text: bool,
+    data: BytesMut,
+}
+
+enum Flow {
+    Continue,
+    Dispatch(WsEvent),
+    Closed(u16, String),
+}
+
+#[inline]
+async fn next_ping(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[inline]
+fn close_with(state: &WsState, code: CloseCode, reason: &str) -> Flow {
+    state.close(Some(CloseReason::from((code, reason))));
+    Flow::Closed(code.into(), reason.to_string())
+}
+
+impl WsRoute {
+    #[inline]
+    fn message(&'static self, state: &Arc<WsState>, text: bool, data: Bytes) -> Flow {
+        if self.on_message.is_none() {
+            return Flow::Continue;
+        }
+
+        if text && simdutf8::basic::from_utf8(&data).is_err() {
+            return close_with(state, CloseCode::Invalid, "Invalid UTF-8");
+        }
+
+        match text {
+            true => Flow::Dispatch(WsEvent::Text(state.clone(), data)),
+            false => Flow::Dispatch(WsEvent::Binary(state.clone(), data)),
+        }
+    }
+
+    fn frame(&'static self, state: &Arc<WsState>, frame: Frame, fragmented: &mut Option<Fragmented>) -> Flow {
+        let (text, data) = match frame {
+            Frame::Text(data) => (true, data),
+            Frame::Binary(data) => (false, data),
+            Frame::Ping(data) => {
+                state.send_frame(encode_frame(Message::Pong(data)));
+                return Flow::Continue;
+            }
+            Frame::Pong(_) => return Flow::Continue,
+            Frame::Close(reason) => {
+                let code = reason.as_ref().map_or(1005, |reason| u16::from(reason.code));
+                let description = reason.as_ref().and_then(|reason| reason.description.clone()).unwrap_or_default();
+
+                state.close(reason.map(|reason| CloseReason::from(reason.code)));
+                return Flow::Closed(code, description);
+            }
+            Frame::Continuation(item) => {
+                let (first, last) = match &item {
+                    Item::FirstText(_) => (Some(true), false),
+                    Item::FirstBinary(_) => (Some(false), false),
+                    Item::Continue(_) => (None, false),
+                    Item::Last(_) => (None, true),
+                };
+
+                let (Item::FirstText(chunk) | Item::FirstBinary(chunk) | Item::Continue(chunk) | Item::Last(chunk)) = item;
+
+                if let Some(text) = first {
+                    *fragmented = Some(Fragmented { text, data: BytesMut::new() });
+                }
+
+                let current = match fragmented {
+                    Some(res) => res,
+                    None => return close_with(state, CloseCode::Protocol, "Unexpected continuation"),
+                };
+
+                if current.data.len() + chunk.len() > self.max_message_size {
+                    return close_with(state, CloseCode::Size, "Message too big");
+                }
+
+                current.data.extend_from_slice(&chunk);
+                if !last {
+                    return Flow::Continue;
+                }
+
+                match fragmented.take() {
+                    Some(res) => (res.text, res.data.freeze()),
+                    None => return Flow::Continue,
+                }
+            }
+        };
+
+        self.message(state, text, data)
+    }
+}
+
+/// Reads frames from the client on the worker thread, pings are answered here and
+/// a ping is sent every interval, a client which hasn't answered the last one is dropped
+async fn read_frames(route: &'static WsRoute, state: Arc<WsState>, mut payload: Payload) {
+    let mut codec = Codec::new().max_size(route.max_message_size);
+    let mut buffer = BytesMut::new();
+    let mut fragmented = None;
+
+    let mut ping = route.ping_interval.map(|period| {
+        let mut interval = interval_at(Instant::now() + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        interval
+    });
+    let mut awaiting_pong = false;
+
+    let (code, reason) = 'read: loop {
+        loop {
+            let flow = match codec.decode(&mut buffer) {
+                Ok(Some(frame)) => {
+                    if matches!(frame, Frame::Pong(_)) {
+                        awaiting_pong = false;
+                    }
+
+                    route.frame(&state, frame, &mut fragmented)
+                }
+                Ok(None) => break,
+                Err(ProtocolError::Overflow) => close_with(&state, CloseCode::Size, "Message too big"),
+                Err(_) => close_with(&state, CloseCode::Protocol, "Protocol error"),
+            };
+
+            match flow {
+                Flow::Continue => {}
+                Flow::Dispatch(event) => {
+                    if !route.dispatch_waiting(event).await {
+                        state.terminate();
+                        break 'read (1006, String::new());
+                    }
+                }
+                Flow::Closed(code, reason) => break 'read (code, reason),
+            }
+        }
+
+        tokio::select! {
+            chunk = payload.next() => match chunk {
+                Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                _ => {
+                    state.terminate();
+                    break (1006, String::new());
+                }
+            },
+            _ = next_ping(&mut ping) => {
+                if awaiting_pong {
+                    state.terminate();
+                    break (1006, String::new());
+                }
+
+                awaiting_pong = true;
+                state.send_frame(encode_frame(Message::Ping(Bytes::new())));
+            },
+            _ = state.shutdown.notified() => {
+                break state.take_server_close().unwrap_or((1006, String::new()));
+            },
+        }
+    };
+
+    leave_all_rooms(&state);
+    route.dispatch_waiting(WsEvent::Close(state, code, reason)).await;
+}
+
+#[inline]
+unsafe fn get_function(env: napi_env, handler: &Option<FnRef>) -> Option<napi_value> {
+    let handler = handler.as_ref()?;
+
+    let mut function = ptr::null_mut();
+    match sys::napi_get_reference_value(env, handler.0, &mut function) {
+        sys::Status::napi_ok => Some(function),
+        _ => None,
+    }
+}
+
+#[inline]
+unsafe fn call_function(env: napi_env, function: napi_value, args: &[napi_value]) -> sys::napi_status {
+    let mut recv = ptr::null_mut();
+    sys::napi_get_undefined(env, &mut recv);
+
+    sys::napi_call_function(env, recv, function, args.len(), args.as_ptr(), ptr::null_mut())
+}
+
+#[inline]
+unsafe fn socket_value(env: napi_env, state: &WsState) -> Option<napi_value> {
+    let js_ref = state.js_ref.lock();
+    let reference = js_ref.as_ref()?;
+
+    let mut socket = ptr::null_mut();
+    match sys::napi_get_reference_value(env, reference.0, &mut socket) {
+        sys::Status::napi_ok => Some(socket),
+        _ => None,
+    }
+}
+
+#[inline]
+unsafe fn release_socket(env: napi_env, state: &WsState) {
+    if let Some(reference) = state.js_ref.lock().take() {
+        sys::napi_delete_reference(env, reference.0);
+    }
+}
+
+unsafe fn open_socket(env: napi_env, route: &WsRoute, blob: &mut RequestBlob, blob_ref: napi_ref, state: Arc<WsState>, body: StreamBody) {
+    let mut request = ptr::null_mut();
+    if sys::napi_get_reference_value(env, blob_ref, &mut request) != sys::Status::napi_ok {
+        let _ = blob.send_result(InnerResp::ServerError);
+        return;
+    }
+
+    let socket = match WsConnection::to_napi_value(env, WsConnection { state: state.clone() }) {
+        Ok(res) => res,
+        Err(_) => {
+            let _ = blob.send_result(InnerResp::ServerError);
+            return;
+        }
+    };
+
+    // The socket is kept alive until it closes so it can be passed to the other handlers
+    let mut reference = ptr::null_mut();
+    if sys::napi_create_reference(env, socket, 1, &mut reference) == sys::Status::napi_ok {
+        *state.js_ref.lock() = Some(JsRef(reference));
+    }
+
+    let status = match get_function(env, &route.on_open) {
+        Some(function) => call_function(env, function, &[socket, request]),
+        None => sys::Status::napi_ok,
+    };
+
+    if blob.sent {
+        release_socket(env, &state);
+        return;
+    }
+
+    if status != sys::Status::napi_ok {
+        release_socket(env, &state);
+        let _ = blob.send_result(InnerResp::ServerError);
+        return;
+    }
+
+    let _ = blob.send_result(InnerResp::WebSocket(body));
+}
+
+unsafe fn receive_message(env: napi_env, route: &WsRoute, state: &WsState, text: bool, data: Bytes) {
+    let (function, socket) = match (get_function(env, &route.on_message), socket_value(env, state)) {
+        (Some(function), Some(socket)) => (function, socket),
+        _ => return,
+    };
+
+    let mut message = ptr::null_mut();
+    let status = match text {
+        true => sys::napi_create_string_utf8(env, data.as_ptr().cast(), data.len(), &mut message),
+        false => sys::napi_create_buffer_copy(env, data.len(), data.as_ptr().cast(), ptr::null_mut(), &mut message),
+    };
+
+    if status == sys::Status::napi_ok {
+        call_function(env, function, &[socket, message]);
+    }
+}
+
+unsafe fn close_socket(env: napi_env, route: &WsRoute, state: &WsState, code: u16, reason: &str) {
+    if let (Some(function), Some(socket)) = (get_function(env, &route.on_close), socket_value(env, state)) {
+        let mut code_value = ptr::null_mut();
+        let mut reason_value = ptr::null_mut();
+
+        sys::napi_create_uint32(env, code as u32, &mut code_value);
+        sys::napi_create_string_utf8(env, reason.as_ptr().cast(), reason.len(), &mut reason_value);
+
+        call_function(env, function, &[socket, code_value, reason_value]);
+    }
+
+    release_socket(env, state);
+}
+
+/// The `call_js` callback for WebSocket routes
+pub(crate) unsafe extern "C" fn call_ws_handler(
+    raw_env: napi_env,
+    _js_callback: napi_value,
+    _context: *mut c_void,
+    data: *mut c_void,
+) {
+    let WsCall { route, event } = *Box::from_raw(data as *mut WsCall);
+
+    // env can be null when shutting down
+    if raw_env.is_null() {
+        return;
+    }
+
+    match event {
+        WsEvent::Open { blob, blob_ref, state, body } => open_socket(raw_env, route, &mut *blob, blob_ref, state, body),
+        WsEvent::Text(state, data) => receive_message(raw_env, route, &state, true, data),
+        WsEvent::Binary(state, data) => receive_message(raw_env, route, &state, false, data),
+        WsEvent::Close(state, code, reason) => close_socket(raw_env, route, &state, code, &reason),
+    }
+}